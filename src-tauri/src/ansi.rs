@@ -0,0 +1,263 @@
+//! ANSI escape sequence handling for colored log output.
+
+use crate::models::StyledSpan;
+
+const ESC: char = '\x1b';
+
+/// The 8 basic colors, in SGR order
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Whether the text contains escape sequences at all
+pub fn has_escapes(text: &str) -> bool {
+    text.contains(ESC)
+}
+
+/// Remove every escape sequence from `text`
+pub fn strip(text: &str) -> String {
+    parse(text).into_iter().map(|span| span.text).collect()
+}
+
+/// Split `text` into runs of identically styled text, interpreting SGR
+/// (color, bold...) sequences and dropping every other escape sequence
+pub fn parse(text: &str) -> Vec<StyledSpan> {
+    let mut spans: Vec<StyledSpan> = Vec::new();
+    let mut style = StyledSpan::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            match spans.last_mut() {
+                Some(last) if same_style(last, &style) => last.text.push(c),
+                _ => spans.push(StyledSpan {
+                    text: c.to_string(),
+                    ..style.clone()
+                }),
+            }
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters and intermediates, then a final byte in @..~
+            Some('[') => {
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        if c == 'm' {
+                            apply_sgr(&mut style, &params);
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            // OSC (e.g. hyperlinks): terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Any other two-character sequence
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+/// The part of `spans` between the byte offsets `start` and `end` of their
+/// text, keeping the styling
+pub fn slice(spans: &[StyledSpan], start: usize, end: usize) -> Vec<StyledSpan> {
+    let mut offset = 0;
+    let mut sliced = Vec::new();
+
+    for span in spans {
+        let (from, to) = (offset, offset + span.text.len());
+        offset = to;
+        if to <= start || from >= end {
+            continue;
+        }
+        sliced.push(StyledSpan {
+            text: span.text[start.max(from) - from..end.min(to) - from].to_string(),
+            ..span.clone()
+        });
+    }
+
+    sliced
+}
+
+fn same_style(a: &StyledSpan, b: &StyledSpan) -> bool {
+    a.fg == b.fg
+        && a.bg == b.bg
+        && a.bold == b.bold
+        && a.dim == b.dim
+        && a.italic == b.italic
+        && a.underline == b.underline
+}
+
+/// Update `style` with the parameters of an SGR (`ESC [ ... m`) sequence
+fn apply_sgr(style: &mut StyledSpan, params: &str) {
+    let codes: Vec<u32> = params
+        .split([';', ':'])
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let mut codes = codes.into_iter();
+
+    // `ESC [ m` is a reset
+    let mut code = codes.next().unwrap_or(0);
+    loop {
+        match code {
+            0 => *style = StyledSpan::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            30..=37 => style.fg = Some(COLORS[(code - 30) as usize].to_string()),
+            40..=47 => style.bg = Some(COLORS[(code - 40) as usize].to_string()),
+            90..=97 => style.fg = Some(format!("bright_{}", COLORS[(code - 90) as usize])),
+            100..=107 => style.bg = Some(format!("bright_{}", COLORS[(code - 100) as usize])),
+            38 => style.fg = extended_color(&mut codes),
+            48 => style.bg = extended_color(&mut codes),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            _ => {}
+        }
+
+        match codes.next() {
+            Some(next) => code = next,
+            None => break,
+        }
+    }
+}
+
+/// Read a `5;n` (256 colors) or `2;r;g;b` (true color) color
+fn extended_color(codes: &mut impl Iterator<Item = u32>) -> Option<String> {
+    match codes.next()? {
+        5 => Some(palette_color(codes.next()?)),
+        2 => {
+            let (r, g, b) = (codes.next()?, codes.next()?, codes.next()?);
+            Some(format!(
+                "#{:02x}{:02x}{:02x}",
+                r.min(255),
+                g.min(255),
+                b.min(255)
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Name or hex value of a color of the 256-color palette
+fn palette_color(index: u32) -> String {
+    match index {
+        0..=7 => COLORS[index as usize].to_string(),
+        8..=15 => format!("bright_{}", COLORS[(index - 8) as usize]),
+        // 6x6x6 color cube
+        16..=231 => {
+            let level = |v: u32| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(i / 36),
+                level((i / 6) % 6),
+                level(i % 6)
+            )
+        }
+        // Grayscale ramp
+        _ => {
+            let v = 8 + (index.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text and foreground color of each span
+    fn colored(text: &str) -> Vec<(String, Option<String>)> {
+        parse(text)
+            .into_iter()
+            .map(|span| (span.text, span.fg))
+            .collect()
+    }
+
+    fn span(text: &str, fg: Option<&str>) -> (String, Option<String>) {
+        (text.to_string(), fg.map(str::to_string))
+    }
+
+    #[test]
+    fn splits_runs_by_style() {
+        assert_eq!(
+            colored("\x1b[31mred\x1b[0m plain \x1b[1;38;5;208morange"),
+            [
+                span("red", Some("red")),
+                span(" plain ", None),
+                span("orange", Some("#ff8700")),
+            ]
+        );
+
+        let spans = parse("\x1b[1;4;48;2;1;2;3mx\x1b[22;24;49my");
+        assert!(spans[0].bold && spans[0].underline);
+        assert_eq!(spans[0].bg.as_deref(), Some("#010203"));
+        assert!(!spans[1].bold && !spans[1].underline && spans[1].bg.is_none());
+    }
+
+    #[test]
+    fn slices_keep_their_styles() {
+        let spans = parse("\x1b[31mred\x1b[0m plain \x1b[32mgreen");
+        let sliced: Vec<_> = slice(&spans, 1, 12)
+            .into_iter()
+            .map(|span| (span.text, span.fg))
+            .collect();
+        assert_eq!(
+            sliced,
+            [
+                span("ed", Some("red")),
+                span(" plain ", None),
+                span("gr", Some("green")),
+            ]
+        );
+        assert!(slice(&spans, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn drops_other_sequences() {
+        assert_eq!(
+            strip("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07 \x1b[2Kdone\x1b="),
+            "link done"
+        );
+    }
+
+    #[test]
+    fn escapes_cut_off_at_the_end_of_the_line() {
+        // The line ends in the middle of an escape sequence
+        assert_eq!(colored("ok\x1b[31"), [span("ok", None)]);
+        assert_eq!(colored("ok\x1b["), [span("ok", None)]);
+        assert_eq!(colored("ok\x1b"), [span("ok", None)]);
+        assert_eq!(strip("ok\x1b]8;;https://exa"), "ok");
+        // An unfinished SGR doesn't color what came before it
+        assert_eq!(colored("\x1b[32mok\x1b[31"), [span("ok", Some("green"))]);
+    }
+
+    #[test]
+    fn escapes_cut_off_at_the_start_of_the_line() {
+        // The rest of a sequence begun on the previous line is plain text
+        assert_eq!(colored("31mred\x1b[0m"), [span("31mred", None)]);
+        assert!(!has_escapes("31mred"));
+    }
+}
@@ -1,3 +1,5 @@
+use kube::runtime::watcher;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +10,9 @@ pub enum K8sError {
     #[error("Config error: {0}")]
     Config(#[from] kube::config::KubeconfigError),
 
+    #[error("Watch error: {0}")]
+    Watch(#[from] watcher::Error),
+
     #[error("Infer config error: {0}")]
     InferConfig(#[from] kube::config::InferConfigError),
 
@@ -16,6 +21,305 @@ pub enum K8sError {
 
     #[error("No kubeconfig found")]
     NoKubeconfig,
+
+    #[error("Settings error: {0}")]
+    Settings(String),
+
+    #[error("Invalid label selector: {0}")]
+    InvalidSelector(String),
+
+    #[error("Invalid query: {0}")]
+    Query(#[from] crate::query::QueryError),
+
+    #[error("Invalid pattern: {0}")]
+    Pattern(#[from] regex::Error),
+
+    #[error("Invalid time range: {0}")]
+    InvalidTimeRange(String),
+
+    #[error("Invalid log cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("Revision {revision} of deployment {deployment} not found")]
+    RevisionNotFound { deployment: String, revision: i64 },
+
+    #[error("Metrics unavailable: {0}")]
+    MetricsUnavailable(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    /// An error annotated with the context, namespace and resource involved
+    #[error("{source}")]
+    Scoped {
+        source: Box<K8sError>,
+        scope: ErrorScope,
+    },
+}
+
+/// Where an error happened, as far as the failing command knows
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ErrorScope {
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    /// `<resource>/<name>`, e.g. `pods/api-7d9f`
+    pub resource: Option<String>,
+}
+
+impl ErrorScope {
+    pub fn context(context: &str) -> Self {
+        Self {
+            context: Some(context.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn namespaced(context: &str, namespace: &str) -> Self {
+        Self {
+            namespace: Some(namespace.to_string()),
+            ..Self::context(context)
+        }
+    }
+
+    pub fn resource(mut self, resource: &str, name: &str) -> Self {
+        self.resource = Some(format!("{}/{}", resource, name));
+        self
+    }
+}
+
+/// Stable error category the frontend can switch on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    /// Any other error status returned by the API server
+    Api,
+    Timeout,
+    Connection,
+    AuthPlugin,
+    Kubeconfig,
+    /// Search query that doesn't parse; the error carries the offending span
+    InvalidQuery,
+    /// Invalid selector, pattern, time range or cursor, or an unreadable API response
+    Parse,
+    Settings,
+    Io,
+    /// metrics-server is not installed or not responding
+    MetricsUnavailable,
+    Cancelled,
+}
+
+impl K8sError {
+    /// Attach a scope to the error. Fields already set by a narrower scope are kept.
+    pub fn with_scope(self, scope: &ErrorScope) -> Self {
+        match self {
+            K8sError::Scoped {
+                source,
+                scope: inner,
+            } => K8sError::Scoped {
+                source,
+                scope: ErrorScope {
+                    context: inner.context.or_else(|| scope.context.clone()),
+                    namespace: inner.namespace.or_else(|| scope.namespace.clone()),
+                    resource: inner.resource.or_else(|| scope.resource.clone()),
+                },
+            },
+            error => K8sError::Scoped {
+                source: Box::new(error),
+                scope: scope.clone(),
+            },
+        }
+    }
+
+    /// The error without its scope
+    fn unscoped(&self) -> &K8sError {
+        match self {
+            K8sError::Scoped { source, .. } => source.unscoped(),
+            error => error,
+        }
+    }
+
+    fn scope(&self) -> Option<&ErrorScope> {
+        match self {
+            K8sError::Scoped { scope, .. } => Some(scope),
+            _ => None,
+        }
+    }
+
+    /// The Kubernetes `Status` returned by the API server, if any
+    fn api_status(&self) -> Option<&kube::core::ErrorResponse> {
+        match self.unscoped() {
+            K8sError::Watch(watcher::Error::WatchError(response)) => Some(response),
+            _ => match self.client_error() {
+                Some(kube::Error::Api(response)) => Some(response),
+                _ => None,
+            },
+        }
+    }
+
+    /// The client error behind a request or watch failure
+    fn client_error(&self) -> Option<&kube::Error> {
+        match self.unscoped() {
+            K8sError::Kube(error)
+            | K8sError::Watch(
+                watcher::Error::InitialListFailed(error)
+                | watcher::Error::WatchStartFailed(error)
+                | watcher::Error::WatchFailed(error),
+            ) => Some(error),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        if let Some(response) = self.api_status() {
+            return match response.code {
+                401 => ErrorKind::Unauthorized,
+                403 => ErrorKind::Forbidden,
+                404 => ErrorKind::NotFound,
+                409 => ErrorKind::Conflict,
+                408 | 504 => ErrorKind::Timeout,
+                _ => ErrorKind::Api,
+            };
+        }
+
+        if is_timeout(self) {
+            return ErrorKind::Timeout;
+        }
+
+        if let Some(error) = self.client_error() {
+            return match error {
+                kube::Error::Auth(_) => ErrorKind::AuthPlugin,
+                kube::Error::InferConfig(_) => ErrorKind::Kubeconfig,
+                kube::Error::SerdeError(_)
+                | kube::Error::FromUtf8(_)
+                | kube::Error::LinesCodecMaxLineLengthExceeded => ErrorKind::Parse,
+                _ => ErrorKind::Connection,
+            };
+        }
+
+        match self.unscoped() {
+            // Client errors are handled above, status errors by `api_status`
+            K8sError::Kube(_) | K8sError::Watch(_) => ErrorKind::Api,
+            K8sError::Config(_) | K8sError::InferConfig(_) | K8sError::NoKubeconfig => {
+                ErrorKind::Kubeconfig
+            }
+            K8sError::Io(_) => ErrorKind::Io,
+            K8sError::Settings(_) => ErrorKind::Settings,
+            K8sError::Query(_) => ErrorKind::InvalidQuery,
+            K8sError::InvalidSelector(_)
+            | K8sError::Pattern(_)
+            | K8sError::InvalidTimeRange(_)
+            | K8sError::InvalidCursor(_) => ErrorKind::Parse,
+            K8sError::RevisionNotFound { .. } => ErrorKind::NotFound,
+            K8sError::MetricsUnavailable(_) => ErrorKind::MetricsUnavailable,
+            K8sError::Cancelled => ErrorKind::Cancelled,
+            K8sError::Scoped { .. } => unreachable!("unscoped() strips scopes"),
+        }
+    }
+
+    /// Whether retrying the same call may succeed. A 401 counts, since the
+    /// client is rebuilt with fresh credentials on the next call.
+    pub fn retryable(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Unauthorized
+            | ErrorKind::Conflict
+            | ErrorKind::Timeout
+            | ErrorKind::Connection => true,
+            ErrorKind::Api => self
+                .api_status()
+                .is_some_and(|r| r.code == 429 || r.code >= 500),
+            _ => false,
+        }
+    }
+}
+
+/// Whether a timeout shows up anywhere in the error's source chain
+fn is_timeout(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(io) = error.downcast_ref::<std::io::Error>() {
+            if io.kind() == std::io::ErrorKind::TimedOut {
+                return true;
+            }
+        }
+        if error.is::<tokio::time::error::Elapsed>() {
+            return true;
+        }
+        current = error.source();
+    }
+    false
+}
+
+/// Attach an `ErrorScope` to the error of a result
+pub trait InScope<T> {
+    fn in_scope(self, scope: &ErrorScope) -> Result<T, K8sError>;
+}
+
+impl<T, E: Into<K8sError>> InScope<T> for Result<T, E> {
+    fn in_scope(self, scope: &ErrorScope) -> Result<T, K8sError> {
+        self.map_err(|e| e.into().with_scope(scope))
+    }
+}
+
+/// Shape of an error as seen by the frontend, both when a command fails and
+/// when a stream or search reports a failure along with its results
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SerializedError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// HTTP status code of the API response
+    pub status: Option<u16>,
+    /// `reason` of the Kubernetes `Status`, e.g. `NotFound`
+    pub reason: Option<String>,
+    /// `message` of the Kubernetes `Status`
+    pub api_message: Option<String>,
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    pub resource: Option<String>,
+    pub retryable: bool,
+    /// Character offsets of the offending part of a search query
+    pub span: Option<QuerySpan>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuerySpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<&K8sError> for SerializedError {
+    fn from(error: &K8sError) -> Self {
+        let status = error.api_status();
+        let scope = error.scope();
+        let non_empty = |text: &str| Some(text.to_string()).filter(|t| !t.is_empty());
+
+        SerializedError {
+            kind: error.kind(),
+            message: error.to_string(),
+            status: status.map(|s| s.code),
+            reason: status.and_then(|s| non_empty(&s.reason)),
+            api_message: status.and_then(|s| non_empty(&s.message)),
+            context: scope.and_then(|s| s.context.clone()),
+            namespace: scope.and_then(|s| s.namespace.clone()),
+            resource: scope.and_then(|s| s.resource.clone()),
+            retryable: error.retryable(),
+            span: match error.unscoped() {
+                K8sError::Query(error) => Some(QuerySpan {
+                    start: error.start,
+                    end: error.end,
+                }),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl From<K8sError> for SerializedError {
+    fn from(error: K8sError) -> Self {
+        SerializedError::from(&error)
+    }
 }
 
 impl serde::Serialize for K8sError {
@@ -23,6 +327,134 @@ impl serde::Serialize for K8sError {
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        SerializedError::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn api_error(code: u16, reason: &str) -> K8sError {
+        K8sError::Kube(kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".to_string(),
+            message: format!("pods \"api\" is {}", reason),
+            reason: reason.to_string(),
+            code,
+        }))
+    }
+
+    #[test]
+    fn api_statuses_map_to_kinds() {
+        let cases = [
+            (401, ErrorKind::Unauthorized, true),
+            (403, ErrorKind::Forbidden, false),
+            (404, ErrorKind::NotFound, false),
+            (409, ErrorKind::Conflict, true),
+            (504, ErrorKind::Timeout, true),
+            (429, ErrorKind::Api, true),
+            (500, ErrorKind::Api, true),
+            (422, ErrorKind::Api, false),
+        ];
+        for (code, kind, retryable) in cases {
+            let error = api_error(code, "Invalid");
+            assert_eq!(error.kind(), kind, "status {}", code);
+            assert_eq!(error.retryable(), retryable, "status {}", code);
+        }
+    }
+
+    #[test]
+    fn other_errors_map_to_kinds() {
+        let timed_out = std::io::Error::new(std::io::ErrorKind::TimedOut, "read timed out");
+        assert_eq!(K8sError::Io(timed_out).kind(), ErrorKind::Timeout);
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(K8sError::Io(denied).kind(), ErrorKind::Io);
+
+        assert_eq!(K8sError::NoKubeconfig.kind(), ErrorKind::Kubeconfig);
+        assert_eq!(K8sError::Settings("x".into()).kind(), ErrorKind::Settings);
+        assert_eq!(
+            K8sError::InvalidSelector("x".into()).kind(),
+            ErrorKind::Parse
+        );
+        assert_eq!(K8sError::Cancelled.kind(), ErrorKind::Cancelled);
+        assert!(!K8sError::Cancelled.retryable());
+    }
+
+    #[test]
+    fn watch_errors_map_like_their_cause() {
+        let status = kube::core::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "forbidden".to_string(),
+            reason: "Forbidden".to_string(),
+            code: 403,
+        };
+        let error = K8sError::from(watcher::Error::WatchError(status));
+        assert_eq!(error.kind(), ErrorKind::Forbidden);
+
+        let K8sError::Kube(cause) = api_error(401, "Unauthorized") else {
+            unreachable!()
+        };
+        let error = K8sError::from(watcher::Error::InitialListFailed(cause));
+        assert_eq!(error.kind(), ErrorKind::Unauthorized);
+        assert!(error.retryable());
+        assert_eq!(
+            K8sError::from(watcher::Error::NoResourceVersion).kind(),
+            ErrorKind::Api
+        );
+    }
+
+    #[test]
+    fn scopes_keep_the_narrowest_fields() {
+        let pod = ErrorScope::namespaced("dev", "shop").resource("pods", "api-7d9f");
+        let error = Err::<(), _>(api_error(404, "NotFound"))
+            .in_scope(&pod)
+            .unwrap_err()
+            .with_scope(&ErrorScope::namespaced("prod", "other"));
+
+        let scope = error.scope().unwrap();
+        assert_eq!(scope.context.as_deref(), Some("dev"));
+        assert_eq!(scope.namespace.as_deref(), Some("shop"));
+        assert_eq!(scope.resource.as_deref(), Some("pods/api-7d9f"));
+        // Scoping changes neither the kind nor the message
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(error.to_string(), api_error(404, "NotFound").to_string());
+    }
+
+    #[test]
+    fn serializes_kind_status_and_scope() {
+        let error = api_error(404, "NotFound")
+            .with_scope(&ErrorScope::namespaced("dev", "shop").resource("pods", "api"));
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "kind": "NotFound",
+                "message": error.to_string(),
+                "status": 404,
+                "reason": "NotFound",
+                "api_message": "pods \"api\" is NotFound",
+                "context": "dev",
+                "namespace": "shop",
+                "resource": "pods/api",
+                "retryable": false,
+                "span": null,
+            })
+        );
+
+        let value = serde_json::to_value(K8sError::Cancelled).unwrap();
+        assert_eq!(value["kind"], "Cancelled");
+        assert_eq!(value["status"], serde_json::Value::Null);
+        assert_eq!(value["context"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn query_errors_carry_their_span() {
+        let error = K8sError::from(crate::query::Query::parse("level:error (timeout").unwrap_err())
+            .with_scope(&ErrorScope::context("dev"));
+        let value = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(value["kind"], "InvalidQuery");
+        assert_eq!(value["span"], json!({ "start": 12, "end": 13 }));
     }
 }
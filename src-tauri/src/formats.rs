@@ -0,0 +1,413 @@
+//! Parsers for common infrastructure log formats: klog/glog, nginx/Apache
+//! access logs, Envoy access logs, PostgreSQL and Redis.
+
+use chrono::{DateTime, NaiveDateTime};
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// A built-in line format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `I1026 12:00:00.000000 1 file.go:12] msg`
+    Klog,
+    /// nginx/Apache common and combined access logs
+    Access,
+    /// Envoy's default access log format
+    Envoy,
+    Postgres,
+    Redis,
+}
+
+/// A line split by one of the built-in formats
+pub struct Parsed {
+    /// Timestamp of the line, when it has one: RFC 3339 for formats with a
+    /// full date, klog's `MMDD hh:mm:ss.uuuuuu` token as written. Times the
+    /// format writes in the container's local time have no offset (and klog's
+    /// no year), so `timestamp::parse` fills those in from the kubectl
+    /// timestamp when normalizing.
+    pub timestamp: Option<String>,
+    /// Level as written by the format, e.g. `W` becomes `WARN`
+    pub level: Option<String>,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Format {
+    /// Formats in the order auto-detection tries them
+    pub const ALL: [Format; 5] = [
+        Format::Klog,
+        Format::Envoy,
+        Format::Access,
+        Format::Postgres,
+        Format::Redis,
+    ];
+
+    /// Look a format up by the name used in parser assignments
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "klog" | "glog" => Some(Format::Klog),
+            "access" | "nginx" | "apache" => Some(Format::Access),
+            "envoy" => Some(Format::Envoy),
+            "postgres" | "postgresql" => Some(Format::Postgres),
+            "redis" => Some(Format::Redis),
+            _ => None,
+        }
+    }
+
+    /// Parse a line (without its kubectl timestamp) in this format
+    pub fn parse(self, line: &str) -> Option<Parsed> {
+        match self {
+            Format::Klog => parse_klog(line),
+            Format::Access => parse_access(line),
+            Format::Envoy => parse_envoy(line),
+            Format::Postgres => parse_postgres(line),
+            Format::Redis => parse_redis(line),
+        }
+    }
+}
+
+/// Parse a line with the first built-in format it matches
+pub fn detect(line: &str) -> Option<Parsed> {
+    Format::ALL.iter().find_map(|format| format.parse(line))
+}
+
+fn klog() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^(?P<level>[IWEF])(?P<month>\d{2})(?P<day>\d{2}) (?P<time>\d{2}:\d{2}:\d{2}\.\d{6})\s+(?P<thread>\d+) (?P<caller>[^ \]]+:\d+)\] ?(?P<message>.*)$",
+        )
+        .expect("valid regex")
+    })
+}
+
+fn access() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"^(?P<remote_addr>\S+) (?P<ident>\S+) (?P<remote_user>\S+) \[(?P<time>[^\]]+)\] "(?P<request>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)")?"#,
+        )
+        .expect("valid regex")
+    })
+}
+
+fn envoy() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"^\[(?P<time>[^\]]+)\] "(?P<request>[^"]*)" (?P<status>\d+) (?P<response_flags>\S+) (?P<bytes_received>\d+) (?P<bytes_sent>\d+) (?P<duration_ms>\d+) (?P<upstream_service_time>\S+) "(?P<x_forwarded_for>[^"]*)" "(?P<user_agent>[^"]*)" "(?P<request_id>[^"]*)" "(?P<authority>[^"]*)" "(?P<upstream_host>[^"]*)""#,
+        )
+        .expect("valid regex")
+    })
+}
+
+fn postgres() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^(?P<time>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?)(?: (?P<zone>[A-Za-z]+|[+-]\d{2}(?::?\d{2})?))? \[(?P<pid>\d+)\](?:[:-]\d+)? (?:(?P<user>[^@\s]*)@(?P<database>\S*) )?(?P<level>DEBUG\d?|LOG|INFO|NOTICE|WARNING|ERROR|FATAL|PANIC|DETAIL|HINT|STATEMENT|CONTEXT|QUERY|LOCATION):\s+(?P<message>.*)$",
+        )
+        .expect("valid regex")
+    })
+}
+
+fn redis() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^(?P<pid>\d+):(?P<role>[XCSM]) (?P<time>\d{1,2} [A-Z][a-z]{2} \d{4} \d{2}:\d{2}:\d{2}\.\d{3}) (?P<level>[.\-*#]) (?P<message>.*)$",
+        )
+        .expect("valid regex")
+    })
+}
+
+/// Named groups of `captures` other than those listed in `skip`, in pattern order
+fn named_fields(regex: &Regex, captures: &Captures, skip: &[&str]) -> Vec<(String, String)> {
+    regex
+        .capture_names()
+        .flatten()
+        .filter(|name| !skip.contains(name))
+        .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().to_string())))
+        .collect()
+}
+
+/// Level of an HTTP access log line from its status class
+fn status_level(status: &str) -> &'static str {
+    match status.as_bytes().first() {
+        Some(b'5') => "ERROR",
+        Some(b'4') => "WARN",
+        // 0 means no response was sent at all
+        Some(b'0') => "ERROR",
+        _ => "INFO",
+    }
+}
+
+/// Split `GET /path HTTP/1.1` into method, path and protocol fields
+fn request_fields(request: &str, fields: &mut Vec<(String, String)>) {
+    let mut parts = request.split(' ');
+    if let (Some(method), Some(path), Some(protocol), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    {
+        fields.push(("method".to_string(), method.to_string()));
+        fields.push(("path".to_string(), path.to_string()));
+        fields.push(("protocol".to_string(), protocol.to_string()));
+    }
+}
+
+fn parse_klog(line: &str) -> Option<Parsed> {
+    let captures = klog().captures(line)?;
+    let level = match &captures["level"] {
+        "I" => "INFO",
+        "W" => "WARN",
+        "E" => "ERROR",
+        _ => "FATAL",
+    };
+
+    // klog leaves out the year and the offset, which only `timestamp_utc` gets
+    let timestamp = format!(
+        "{}{} {}",
+        &captures["month"], &captures["day"], &captures["time"]
+    );
+
+    Some(Parsed {
+        timestamp: Some(timestamp),
+        level: Some(level.to_string()),
+        message: captures["message"].to_string(),
+        fields: named_fields(
+            klog(),
+            &captures,
+            &["level", "month", "day", "time", "message"],
+        ),
+    })
+}
+
+fn parse_access(line: &str) -> Option<Parsed> {
+    let captures = access().captures(line)?;
+    let status = &captures["status"];
+    let timestamp = DateTime::parse_from_str(&captures["time"], "%d/%b/%Y:%H:%M:%S %z")
+        .ok()
+        .map(|time| time.to_rfc3339());
+
+    let mut fields = named_fields(access(), &captures, &["ident", "time", "request"]);
+    request_fields(&captures["request"], &mut fields);
+    fields.retain(|(_, value)| value != "-");
+
+    Some(Parsed {
+        timestamp,
+        level: Some(status_level(status).to_string()),
+        message: format!("{} {}", &captures["request"], status),
+        fields,
+    })
+}
+
+fn parse_envoy(line: &str) -> Option<Parsed> {
+    let captures = envoy().captures(line)?;
+    let status = &captures["status"];
+    let timestamp = DateTime::parse_from_rfc3339(&captures["time"])
+        .ok()
+        .map(|time| time.to_rfc3339());
+
+    let mut fields = named_fields(envoy(), &captures, &["time", "request"]);
+    request_fields(&captures["request"], &mut fields);
+    fields.retain(|(_, value)| value != "-");
+
+    Some(Parsed {
+        timestamp,
+        level: Some(status_level(status).to_string()),
+        message: format!("{} {}", &captures["request"], status),
+        fields,
+    })
+}
+
+fn parse_postgres(line: &str) -> Option<Parsed> {
+    let captures = postgres().captures(line)?;
+    let level = match &captures["level"] {
+        "LOG" | "INFO" | "NOTICE" => Some("INFO"),
+        "WARNING" => Some("WARN"),
+        "ERROR" => Some("ERROR"),
+        "FATAL" | "PANIC" => Some("FATAL"),
+        level if level.starts_with("DEBUG") => Some("DEBUG"),
+        // DETAIL, HINT, STATEMENT... complete the previous message
+        _ => None,
+    };
+
+    // Only zones chrono understands can be normalized; named zones other than
+    // UTC are ambiguous
+    let time = &captures["time"];
+    let timestamp = match captures.name("zone").map(|z| z.as_str()) {
+        Some("UTC" | "GMT") => NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .map(|time| time.and_utc().to_rfc3339()),
+        // Local time
+        None => Some(time.to_string()),
+        Some(zone) if zone.starts_with(['+', '-']) => {
+            DateTime::parse_from_str(&format!("{} {}", time, zone), "%Y-%m-%d %H:%M:%S%.f %#z")
+                .ok()
+                .map(|time| time.to_rfc3339())
+        }
+        Some(_) => None,
+    };
+
+    let mut fields = named_fields(postgres(), &captures, &["time", "zone", "level", "message"]);
+    fields.retain(|(_, value)| !value.is_empty());
+    if level.is_none() {
+        fields.push(("severity".to_string(), captures["level"].to_string()));
+    }
+
+    Some(Parsed {
+        timestamp,
+        level: level.map(str::to_string),
+        message: captures["message"].to_string(),
+        fields,
+    })
+}
+
+fn parse_redis(line: &str) -> Option<Parsed> {
+    let captures = redis().captures(line)?;
+    let level = match &captures["level"] {
+        "." | "-" => "DEBUG",
+        "*" => "INFO",
+        _ => "WARN",
+    };
+    let role = match &captures["role"] {
+        "M" => "master",
+        "S" => "replica",
+        "C" => "child",
+        _ => "sentinel",
+    };
+    // Local time, without an offset
+    let timestamp = NaiveDateTime::parse_from_str(&captures["time"], "%d %b %Y %H:%M:%S%.3f")
+        .ok()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.3f").to_string());
+
+    Some(Parsed {
+        timestamp,
+        level: Some(level.to_string()),
+        message: captures["message"].to_string(),
+        fields: vec![
+            ("pid".to_string(), captures["pid"].to_string()),
+            ("role".to_string(), role.to_string()),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field<'a>(parsed: &'a Parsed, name: &str) -> Option<&'a str> {
+        parsed
+            .fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parse_as(format: Format, line: &str) -> Parsed {
+        let parsed = detect(line).expect("line is detected");
+        assert_eq!(
+            Format::ALL.iter().find(|f| f.parse(line).is_some()),
+            Some(&format)
+        );
+        parsed
+    }
+
+    #[test]
+    fn klog() {
+        let parsed = parse_as(
+            Format::Klog,
+            "W1026 12:00:00.123456       1 reflector.go:424] watch ended",
+        );
+        assert_eq!(parsed.timestamp.as_deref(), Some("1026 12:00:00.123456"));
+        assert_eq!(parsed.level.as_deref(), Some("WARN"));
+        assert_eq!(parsed.message, "watch ended");
+        assert_eq!(field(&parsed, "caller"), Some("reflector.go:424"));
+        assert_eq!(field(&parsed, "thread"), Some("1"));
+    }
+
+    #[test]
+    fn access_log() {
+        let parsed = parse_as(
+            Format::Access,
+            r#"10.0.0.1 - bob [26/Oct/2024:14:00:00 +0200] "GET /api?q=1 HTTP/1.1" 503 12 "-" "curl/8.0""#,
+        );
+        assert_eq!(
+            parsed.timestamp.as_deref(),
+            Some("2024-10-26T14:00:00+02:00")
+        );
+        assert_eq!(parsed.level.as_deref(), Some("ERROR"));
+        assert_eq!(parsed.message, "GET /api?q=1 HTTP/1.1 503");
+        assert_eq!(field(&parsed, "method"), Some("GET"));
+        assert_eq!(field(&parsed, "path"), Some("/api?q=1"));
+        assert_eq!(field(&parsed, "remote_user"), Some("bob"));
+        // `-` stands for a missing value
+        assert_eq!(field(&parsed, "referer"), None);
+    }
+
+    #[test]
+    fn envoy() {
+        let parsed = parse_as(
+            Format::Envoy,
+            r#"[2024-10-26T12:00:00.123Z] "POST /orders HTTP/2" 404 NR 10 0 3 - "10.0.0.1" "grpc-go" "abc-123" "orders" "-""#,
+        );
+        assert_eq!(
+            parsed.timestamp.as_deref(),
+            Some("2024-10-26T12:00:00.123+00:00")
+        );
+        assert_eq!(parsed.level.as_deref(), Some("WARN"));
+        assert_eq!(field(&parsed, "response_flags"), Some("NR"));
+        assert_eq!(field(&parsed, "request_id"), Some("abc-123"));
+        assert_eq!(field(&parsed, "upstream_host"), None);
+    }
+
+    #[test]
+    fn postgres() {
+        let parsed = parse_as(
+            Format::Postgres,
+            "2024-10-26 12:00:00.123 UTC [42] app@shop ERROR:  duplicate key value",
+        );
+        assert_eq!(
+            parsed.timestamp.as_deref(),
+            Some("2024-10-26T12:00:00.123+00:00")
+        );
+        assert_eq!(parsed.level.as_deref(), Some("ERROR"));
+        assert_eq!(parsed.message, "duplicate key value");
+        assert_eq!(field(&parsed, "database"), Some("shop"));
+
+        // Without a zone the time is local; continuation severities have no level
+        let parsed = parse_as(
+            Format::Postgres,
+            "2024-10-26 12:00:00.123 [42] DETAIL:  Key (id)=(1) already exists.",
+        );
+        assert_eq!(parsed.timestamp.as_deref(), Some("2024-10-26 12:00:00.123"));
+        assert_eq!(parsed.level, None);
+        assert_eq!(field(&parsed, "severity"), Some("DETAIL"));
+    }
+
+    #[test]
+    fn redis() {
+        let parsed = parse_as(
+            Format::Redis,
+            "1:M 26 Oct 2024 12:00:00.123 # WARNING overcommit_memory is set to 0",
+        );
+        assert_eq!(parsed.timestamp.as_deref(), Some("2024-10-26 12:00:00.123"));
+        assert_eq!(parsed.level.as_deref(), Some("WARN"));
+        assert_eq!(field(&parsed, "role"), Some("master"));
+        assert_eq!(field(&parsed, "pid"), Some("1"));
+    }
+
+    #[test]
+    fn other_lines_are_not_detected() {
+        assert!(detect("Listening on :8080").is_none());
+        assert!(detect(r#"{"level":"info","msg":"hi"}"#).is_none());
+        assert!(detect("level=info msg=hi").is_none());
+    }
+
+    #[test]
+    fn names_and_aliases() {
+        assert_eq!(Format::from_name("GLOG"), Some(Format::Klog));
+        assert_eq!(Format::from_name("nginx"), Some(Format::Access));
+        assert_eq!(Format::from_name("postgresql"), Some(Format::Postgres));
+        assert_eq!(Format::from_name("syslog"), None);
+    }
+}
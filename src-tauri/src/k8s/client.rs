@@ -1,32 +1,230 @@
+use http::StatusCode;
+use kube::client::{ClientBuilder, DynBody};
 use kube::config::{KubeConfigOptions, Kubeconfig};
 use kube::{Client, Config};
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{Mutex, OnceCell};
+use tower::util::MapResponseLayer;
 
-use crate::error::K8sError;
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::settings::{self, SettingsCache};
 
 /// Connection timeout in seconds
 const CONNECTION_TIMEOUT_SECS: u64 = 30;
 
-/// Get path to kubeconfig file
-pub fn get_kubeconfig_path() -> Option<PathBuf> {
-    // Check KUBECONFIG env var first
-    if let Ok(path) = std::env::var("KUBECONFIG") {
-        return Some(PathBuf::from(path));
+/// Get the kubeconfig files to load, in precedence order.
+///
+/// Like kubectl, `KUBECONFIG` may hold a list of paths (`:`-separated, `;` on
+/// Windows) and falls back to `~/.kube/config` when unset. Extra files and
+/// directories from the app settings are appended after those.
+pub fn get_kubeconfig_paths(settings: &SettingsCache) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match std::env::var_os("KUBECONFIG") {
+        Some(value) if !value.is_empty() => std::env::split_paths(&value)
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect(),
+        // Fall back to default location
+        _ => dirs::home_dir()
+            .map(|h| h.join(".kube").join("config"))
+            .into_iter()
+            .collect(),
+    };
+
+    // A broken settings file is reported by `get_settings`; until it's fixed,
+    // only the standard kubeconfig locations are used
+    let extra_paths = settings
+        .get()
+        .map(|s| s.extra_kubeconfig_paths.clone())
+        .unwrap_or_default();
+    for extra in extra_paths {
+        let path = settings::expand_home(&extra);
+        if path.is_dir() {
+            paths.extend(kubeconfig_files_in(&path));
+        } else {
+            paths.push(path);
+        }
     }
-    // Fall back to default location
-    dirs::home_dir().map(|h| h.join(".kube").join("config"))
+
+    // A file listed twice keeps its first (highest precedence) position
+    let mut seen = HashSet::new();
+    paths.retain(|p| seen.insert(p.clone()));
+    paths
+}
+
+/// Regular, non-hidden files of a directory, sorted by name
+fn kubeconfig_files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            !path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    files.sort();
+    files
 }
 
-/// Load kubeconfig from file
-pub fn load_kubeconfig() -> Result<Kubeconfig, K8sError> {
-    let path = get_kubeconfig_path().ok_or(K8sError::NoKubeconfig)?;
-    Ok(Kubeconfig::read_from(&path)?)
+/// Merged kubeconfig along with the file each context was taken from
+pub struct LoadedKubeconfig {
+    pub kubeconfig: Kubeconfig,
+    pub context_sources: HashMap<String, PathBuf>,
 }
 
-/// Create a Kubernetes client for a specific context
-pub async fn get_client_for_context(context_name: &str) -> Result<Client, K8sError> {
-    let kubeconfig = load_kubeconfig()?;
+/// Load and merge all kubeconfig files with kubectl's precedence rules: the
+/// first file to define a context, cluster, user or `current-context` wins.
+/// Files that don't exist are skipped.
+pub fn load_kubeconfig_with_sources(
+    settings: &SettingsCache,
+) -> Result<LoadedKubeconfig, K8sError> {
+    let mut merged: Option<Kubeconfig> = None;
+    let mut context_sources = HashMap::new();
+
+    for path in get_kubeconfig_paths(settings) {
+        if !path.exists() {
+            continue;
+        }
+
+        let config = Kubeconfig::read_from(&path)?;
+        for context in &config.contexts {
+            context_sources
+                .entry(context.name.clone())
+                .or_insert_with(|| path.clone());
+        }
+
+        merged = Some(match merged {
+            Some(merged) => merged.merge(config)?,
+            None => config,
+        });
+    }
+
+    Ok(LoadedKubeconfig {
+        kubeconfig: merged.ok_or(K8sError::NoKubeconfig)?,
+        context_sources,
+    })
+}
+
+/// Load the merged kubeconfig
+pub fn load_kubeconfig(settings: &SettingsCache) -> Result<Kubeconfig, K8sError> {
+    Ok(load_kubeconfig_with_sources(settings)?.kubeconfig)
+}
+
+/// Modification time of every kubeconfig file, used to notice edits
+pub fn kubeconfig_modified(settings: &SettingsCache) -> Vec<(PathBuf, Option<SystemTime>)> {
+    get_kubeconfig_paths(settings)
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Kubernetes clients reused across commands, one per context, so polling and
+/// searches keep warm connections instead of re-reading the kubeconfig and
+/// re-running exec auth plugins on every call.
+///
+/// A client is dropped when the kubeconfig watcher sees its context change or
+/// disappear, and once the API server answers it with 401 Unauthorized.
+pub struct ClientPool {
+    /// One cell per context, so building a client (which may wait on an exec
+    /// auth plugin, e.g. an SSO prompt) only holds up callers of that context
+    clients: Mutex<HashMap<String, Arc<OnceCell<PooledClient>>>>,
+    /// App settings, for the extra kubeconfig files to load contexts from
+    settings: Arc<SettingsCache>,
+}
+
+#[derive(Clone)]
+struct PooledClient {
+    client: Client,
+    /// Same connection settings without a read timeout, for follow log streams
+    follow_client: Client,
+    /// Set by the clients' middleware when a response is 401 Unauthorized
+    unauthorized: Arc<AtomicBool>,
+}
+
+impl ClientPool {
+    pub fn new(settings: Arc<SettingsCache>) -> Self {
+        Self {
+            clients: Mutex::default(),
+            settings,
+        }
+    }
+
+    /// Get the client for a context, creating it on first use
+    pub async fn get(&self, context_name: &str) -> Result<Client, K8sError> {
+        Ok(self.pooled(context_name).await?.client.clone())
+    }
+
+    /// Get the client for following logs. A follow stream of a quiet
+    /// container can go minutes without a line, which kube's read timeout
+    /// would cut off, so this client has none.
+    pub async fn get_for_follow(&self, context_name: &str) -> Result<Client, K8sError> {
+        Ok(self.pooled(context_name).await?.follow_client.clone())
+    }
+
+    async fn pooled(&self, context_name: &str) -> Result<PooledClient, K8sError> {
+        let cell = self.cell(context_name).await;
+
+        // Concurrent callers wait for the same build; a failed build leaves
+        // the cell empty for the next call to try again
+        let pooled = cell
+            .get_or_try_init(|| async {
+                let unauthorized = Arc::new(AtomicBool::new(false));
+                let (client, follow_client) =
+                    build_clients(&self.settings, context_name, unauthorized.clone())
+                        .await
+                        .in_scope(&ErrorScope::context(context_name))?;
+                Ok::<_, K8sError>(PooledClient {
+                    client,
+                    follow_client,
+                    unauthorized,
+                })
+            })
+            .await?;
+
+        Ok(pooled.clone())
+    }
+
+    /// The cell of a context. A cell whose client was rejected with 401 is
+    /// swapped for an empty one, so that a fresh client gets built.
+    async fn cell(&self, context_name: &str) -> Arc<OnceCell<PooledClient>> {
+        let mut clients = self.clients.lock().await;
+        let cell = clients.entry(context_name.to_string()).or_default();
+        if cell
+            .get()
+            .is_some_and(|pooled| pooled.unauthorized.load(Ordering::Relaxed))
+        {
+            *cell = Arc::default();
+        }
+        cell.clone()
+    }
+
+    /// Drop the cached client for a context
+    pub async fn invalidate(&self, context_name: &str) {
+        self.clients.lock().await.remove(context_name);
+    }
+}
+
+/// Create the Kubernetes clients for a specific context: one for API calls
+/// and one for follow log streams. Both flag `unauthorized` when the API
+/// server rejects their credentials.
+async fn build_clients(
+    settings: &SettingsCache,
+    context_name: &str,
+    unauthorized: Arc<AtomicBool>,
+) -> Result<(Client, Client), K8sError> {
+    let kubeconfig = load_kubeconfig(settings)?;
 
     let options = KubeConfigOptions {
         context: Some(context_name.to_string()),
@@ -35,10 +233,28 @@ pub async fn get_client_for_context(context_name: &str) -> Result<Client, K8sErr
 
     let mut config = Config::from_custom_kubeconfig(kubeconfig, &options).await?;
 
-    // Set connection timeout
+    // Set connection timeout. The read timeout is left at kube's default,
+    // which watches stay under by asking the server to end them sooner. Plain
+    // API calls are bounded by the frontend's `invokeWithTimeout` instead.
     config.connect_timeout = Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS));
-    config.read_timeout = Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS));
     config.write_timeout = Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS));
 
-    Ok(Client::try_from(config)?)
+    let watch_auth = MapResponseLayer::new(move |response: http::Response<Box<DynBody>>| {
+        if response.status() == StatusCode::UNAUTHORIZED {
+            unauthorized.store(true, Ordering::Relaxed);
+        }
+        response
+    });
+
+    let mut follow_config = config.clone();
+    follow_config.read_timeout = None;
+
+    Ok((
+        ClientBuilder::try_from(config)?
+            .with_layer(&watch_auth)
+            .build(),
+        ClientBuilder::try_from(follow_config)?
+            .with_layer(&watch_auth)
+            .build(),
+    ))
 }
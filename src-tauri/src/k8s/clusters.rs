@@ -1,13 +1,22 @@
+use tauri::State;
+
 use crate::error::K8sError;
-use crate::k8s::client::load_kubeconfig;
+use crate::k8s::client::{load_kubeconfig_with_sources, LoadedKubeconfig};
 use crate::models::ClusterInfo;
+use crate::state::AppState;
 
 #[tauri::command]
-pub async fn get_clusters() -> Result<Vec<ClusterInfo>, K8sError> {
-    let kubeconfig = load_kubeconfig()?;
+pub async fn get_clusters(state: State<'_, AppState>) -> Result<Vec<ClusterInfo>, K8sError> {
+    let loaded = load_kubeconfig_with_sources(&state.settings)?;
+    Ok(cluster_infos(&loaded))
+}
+
+/// Describe every context of a loaded kubeconfig
+pub(crate) fn cluster_infos(loaded: &LoadedKubeconfig) -> Vec<ClusterInfo> {
+    let kubeconfig = &loaded.kubeconfig;
     let current_context = kubeconfig.current_context.clone();
 
-    let clusters: Vec<ClusterInfo> = kubeconfig
+    kubeconfig
         .contexts
         .iter()
         .filter_map(|ctx| {
@@ -27,9 +36,11 @@ pub async fn get_clusters() -> Result<Vec<ClusterInfo>, K8sError> {
                 name: name.clone(),
                 server,
                 is_current: Some(&name) == current_context.as_ref(),
+                source_file: loaded
+                    .context_sources
+                    .get(&name)
+                    .map(|p| p.display().to_string()),
             })
         })
-        .collect();
-
-    Ok(clusters)
+        .collect()
 }
@@ -0,0 +1,483 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::LogParams;
+use kube::{Api, Client};
+use regex::{Captures, Regex};
+use tauri::State;
+
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::deployments::revision_replica_set;
+use crate::k8s::logs::{
+    fetch_logs, normalize_log_level, parse_log_text, TimeWindow, DEFAULT_SEARCH_PARALLELISM,
+};
+use crate::k8s::pods::list_containers;
+use crate::k8s::workloads::list_workload_pods;
+use crate::models::{
+    CompareOptions, CompareTarget, LogComparison, LogEntry, LogProfile, LogSearchFailure,
+    LogSelection, TemplateChange, TemplateChangeKind, WorkloadKind,
+};
+use crate::parser::{LineParser, ParseConfig};
+use crate::state::AppState;
+
+const DEFAULT_MIN_COUNT: u64 = 3;
+const DEFAULT_MIN_RATIO: f64 = 2.0;
+const DEFAULT_LIMIT: usize = 100;
+const DEFAULT_TAIL_LINES: i64 = 5000;
+
+/// z-score above which a rate increase is not put down to chance
+const MIN_Z_SCORE: f64 = 3.0;
+
+/// Templates are cut to this many characters, so that long messages differing
+/// only near their end still group together
+const MAX_TEMPLATE_CHARS: usize = 300;
+
+/// Compare the logs of two selections, e.g. two rollout revisions, two pods or
+/// the same pods over two time ranges. Reports level counts for each side and
+/// the message templates that are new, vanished or increased in `candidate`.
+#[tauri::command]
+pub async fn compare_logs(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    baseline: LogSelection,
+    candidate: LogSelection,
+    options: CompareOptions,
+) -> Result<LogComparison, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let config = ParseConfig::load(&state.settings, None)?;
+    let tail_lines = options.tail_lines.unwrap_or(DEFAULT_TAIL_LINES);
+
+    let (baseline, candidate) = futures::try_join!(
+        load_side(&client, &context, &namespace, &baseline, &config, tail_lines),
+        load_side(&client, &context, &namespace, &candidate, &config, tail_lines),
+    )?;
+
+    Ok(compare(baseline, candidate, &options))
+}
+
+/// Occurrences of one message template
+struct TemplateStats {
+    count: u64,
+    example: String,
+    level: Option<String>,
+}
+
+/// Entries of one side, reduced to counts
+struct Side {
+    profile: LogProfile,
+    templates: HashMap<String, TemplateStats>,
+}
+
+impl Side {
+    fn new(pods: Vec<String>) -> Self {
+        Self {
+            profile: LogProfile {
+                pods,
+                total_entries: 0,
+                level_counts: BTreeMap::new(),
+                template_count: 0,
+                failures: Vec::new(),
+            },
+            templates: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, entry: &LogEntry) {
+        let level = entry.level.as_deref().map(normalize_log_level);
+
+        self.profile.total_entries += 1;
+        *self
+            .profile
+            .level_counts
+            .entry(level.clone().unwrap_or_else(|| "NONE".to_string()))
+            .or_default() += 1;
+
+        let first_line = entry.message.lines().next().unwrap_or_default();
+        self.templates
+            .entry(template_of(first_line))
+            .or_insert_with(|| TemplateStats {
+                count: 0,
+                example: first_line.trim().to_string(),
+                level,
+            })
+            .count += 1;
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        match self.profile.total_entries {
+            0 => 0.0,
+            total => count as f64 * 1000.0 / total as f64,
+        }
+    }
+}
+
+/// Fetch and tally the logs of one selection
+async fn load_side(
+    client: &Client,
+    context: &str,
+    namespace: &str,
+    selection: &LogSelection,
+    config: &ParseConfig,
+    tail_lines: i64,
+) -> Result<Side, K8sError> {
+    let scope = target_scope(context, namespace, &selection.target);
+    let window = TimeWindow::parse(
+        selection.since_seconds,
+        selection.since_time.as_deref(),
+        selection.until_time.as_deref(),
+    )
+    .in_scope(&scope)?;
+
+    let pod_list = target_pods(client, namespace, &selection.target)
+        .await
+        .in_scope(&scope)?;
+    let only_container = match &selection.target {
+        CompareTarget::Pod { container, .. } => container.as_deref(),
+        _ => None,
+    };
+
+    let parsers: Vec<LineParser> = pod_list
+        .iter()
+        .flat_map(|pod| {
+            list_containers(pod)
+                .into_iter()
+                .filter(|c| only_container.is_none_or(|name| c.name == name))
+                .map(move |c| config.parser_for(pod, &c.name))
+        })
+        .collect();
+
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let mut outcomes = futures::stream::iter(parsers)
+        .map(|parser| {
+            let pods = &pods;
+            async move {
+                let params = LogParams {
+                    timestamps: true,
+                    container: Some(parser.container_name.clone()),
+                    since_seconds: selection.since_seconds,
+                    tail_lines: Some(tail_lines),
+                    ..Default::default()
+                };
+                let result = fetch_logs(pods, &parser.pod_name, params, window)
+                    .await
+                    .map(|logs| parse_log_text(&logs, &parser));
+                (parser, result)
+            }
+        })
+        .buffer_unordered(DEFAULT_SEARCH_PARALLELISM);
+
+    let mut side = Side::new(
+        pod_list
+            .iter()
+            .filter_map(|pod| pod.metadata.name.clone())
+            .collect(),
+    );
+    while let Some((parser, result)) = outcomes.next().await {
+        match result {
+            Ok(entries) => entries.iter().for_each(|entry| side.add(entry)),
+            Err(e) => side.profile.failures.push(LogSearchFailure {
+                pod_name: parser.pod_name,
+                container_name: parser.container_name,
+                error: e.into(),
+            }),
+        }
+    }
+    side.profile.template_count = side.templates.len();
+
+    Ok(side)
+}
+
+fn target_scope(context: &str, namespace: &str, target: &CompareTarget) -> ErrorScope {
+    let scope = ErrorScope::namespaced(context, namespace);
+    match target {
+        CompareTarget::Pod { name, .. } => scope.resource("pods", name),
+        CompareTarget::Revision { deployment, .. } => scope.resource("deployments", deployment),
+        CompareTarget::Workload { workload, name } => scope.resource(workload.resource(), name),
+    }
+}
+
+async fn target_pods(
+    client: &Client,
+    namespace: &str,
+    target: &CompareTarget,
+) -> Result<Vec<Pod>, K8sError> {
+    match target {
+        CompareTarget::Pod { name, .. } => {
+            let pod = Api::namespaced(client.clone(), namespace).get(name).await?;
+            Ok(vec![pod])
+        }
+        CompareTarget::Revision {
+            deployment,
+            revision,
+        } => {
+            let replica_set =
+                revision_replica_set(client, namespace, deployment, *revision).await?;
+            let name = replica_set.metadata.name.unwrap_or_default();
+            list_workload_pods(client, namespace, WorkloadKind::ReplicaSet, &name).await
+        }
+        CompareTarget::Workload { workload, name } => {
+            list_workload_pods(client, namespace, *workload, name).await
+        }
+    }
+}
+
+/// Find the templates whose frequency differs significantly between the sides
+fn compare(baseline: Side, candidate: Side, options: &CompareOptions) -> LogComparison {
+    let min_count = options.min_count.unwrap_or(DEFAULT_MIN_COUNT);
+    let min_ratio = options.min_ratio.unwrap_or(DEFAULT_MIN_RATIO);
+
+    let templates: BTreeSet<&String> = baseline
+        .templates
+        .keys()
+        .chain(candidate.templates.keys())
+        .collect();
+
+    let mut changes: Vec<TemplateChange> = templates
+        .into_iter()
+        .filter_map(|template| {
+            let before = baseline.templates.get(template);
+            let after = candidate.templates.get(template);
+            let baseline_count = before.map_or(0, |s| s.count);
+            let candidate_count = after.map_or(0, |s| s.count);
+            let baseline_rate = baseline.rate(baseline_count);
+            let candidate_rate = candidate.rate(candidate_count);
+
+            let change = match (baseline_count, candidate_count) {
+                (0, count) if count >= min_count => TemplateChangeKind::New,
+                (count, 0) if count >= min_count => TemplateChangeKind::Vanished,
+                (0, _) | (_, 0) => return None,
+                _ if candidate_rate >= baseline_rate * min_ratio
+                    && z_score(
+                        (baseline_count, baseline.profile.total_entries),
+                        (candidate_count, candidate.profile.total_entries),
+                    ) >= MIN_Z_SCORE =>
+                {
+                    TemplateChangeKind::Increased
+                }
+                _ => return None,
+            };
+
+            let stats = after.or(before)?;
+            Some(TemplateChange {
+                change,
+                template: template.clone(),
+                example: stats.example.clone(),
+                level: stats.level.clone(),
+                baseline_count,
+                candidate_count,
+                baseline_rate,
+                candidate_rate,
+            })
+        })
+        .collect();
+
+    changes.sort_by_key(|c| (c.change, Reverse(c.baseline_count.max(c.candidate_count))));
+    changes.truncate(options.limit.unwrap_or(DEFAULT_LIMIT));
+
+    LogComparison {
+        baseline: baseline.profile,
+        candidate: candidate.profile,
+        changes,
+    }
+}
+
+/// Two-proportion z-test of `(count, total)` samples: how many standard errors
+/// the second proportion lies above the first
+fn z_score(first: (u64, u64), second: (u64, u64)) -> f64 {
+    let (count1, total1) = (first.0 as f64, first.1 as f64);
+    let (count2, total2) = (second.0 as f64, second.1 as f64);
+    if total1 == 0.0 || total2 == 0.0 {
+        return 0.0;
+    }
+
+    let pooled = (count1 + count2) / (total1 + total2);
+    let error = (pooled * (1.0 - pooled) * (1.0 / total1 + 1.0 / total2)).sqrt();
+    if error == 0.0 {
+        return 0.0;
+    }
+    (count2 / total2 - count1 / total1) / error
+}
+
+/// Quoted strings, timestamps, UUIDs, IP addresses, hex ids and numbers (with
+/// an optional unit), in order of precedence
+fn variable_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            r#"(?P<str>"[^"]*"|'[^']*')"#,
+            r"|(?P<time>\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?)",
+            r"|(?P<uuid>\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b)",
+            r"|(?P<ip>\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b)",
+            r"|(?P<hex>\b(?:0x[0-9a-fA-F]+|[0-9a-fA-F]{8,})\b)",
+            r"|(?P<num>\b\d+(?:\.\d+)?(?:(?i:ns|us|µs|ms|s|m|h|d|b|k|kb|mb|gb|ki|mi|gi)\b|%|\b))",
+        ))
+        .expect("valid regex")
+    })
+}
+
+/// Reduce a message to a template by replacing its variable parts with
+/// placeholders, e.g. `took 12ms for user 42` becomes `took <num> for user <num>`
+fn template_of(message: &str) -> String {
+    const KINDS: [&str; 6] = ["str", "time", "uuid", "ip", "hex", "num"];
+
+    let template = variable_pattern().replace_all(message, |caps: &Captures| {
+        let kind = KINDS
+            .into_iter()
+            .find(|name| caps.name(name).is_some())
+            .unwrap_or("num");
+        let text = &caps[0];
+
+        match kind {
+            // Words such as `deadbeef` and plain integers aren't hex ids
+            "hex" if text.chars().all(|c| c.is_ascii_alphabetic()) => text.to_string(),
+            "hex" if text.chars().all(|c| c.is_ascii_digit()) => "<num>".to_string(),
+            kind => format!("<{}>", kind),
+        }
+    });
+
+    let template = template.split_whitespace().collect::<Vec<_>>().join(" ");
+    match template.char_indices().nth(MAX_TEMPLATE_CHARS) {
+        Some((end, _)) => template[..end].to_string(),
+        None => template,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(message: &str, level: Option<&str>) -> LogEntry {
+        LogEntry {
+            level: level.map(str::to_string),
+            ..LogEntry::from_message(message)
+        }
+    }
+
+    /// A side with `count` entries of each message
+    fn side(messages: &[(&str, u64)]) -> Side {
+        let mut side = Side::new(vec!["api-7d9f8-x4k2p".to_string()]);
+        for (message, count) in messages {
+            for _ in 0..*count {
+                side.add(&entry(message, Some("info")));
+            }
+        }
+        side.profile.template_count = side.templates.len();
+        side
+    }
+
+    fn changes(comparison: &LogComparison) -> Vec<(TemplateChangeKind, &str)> {
+        comparison
+            .changes
+            .iter()
+            .map(|c| (c.change, c.template.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn templates_replace_variable_parts() {
+        assert_eq!(
+            template_of("took 12ms for user 42 (3.5%)"),
+            "took <num> for user <num> (<num>)"
+        );
+        assert_eq!(
+            template_of("GET from 10.0.0.1:8080 id=1b4e28ba-2fa1-11d2-883f-0016d3cca427"),
+            "GET from <ip> id=<uuid>"
+        );
+        assert_eq!(
+            template_of("at 2024-10-26T12:00:00.123Z key \"a b\" ptr 0xc000123 sha 9f86d081"),
+            "at <time> key <str> ptr <hex> sha <hex>"
+        );
+        // Words made of hex letters stay as they are
+        assert_eq!(
+            template_of("deadbeef   cafebabe  added"),
+            "deadbeef cafebabe added"
+        );
+    }
+
+    #[test]
+    fn long_templates_are_cut() {
+        let message = "x".repeat(MAX_TEMPLATE_CHARS + 50);
+        assert_eq!(template_of(&message).chars().count(), MAX_TEMPLATE_CHARS);
+    }
+
+    #[test]
+    fn sides_count_levels_and_first_lines() {
+        let mut side = Side::new(Vec::new());
+        side.add(&entry("panic: boom\ngoroutine 1 [running]:", Some("error")));
+        side.add(&entry("panic: boom\ngoroutine 7 [running]:", Some("ERR")));
+        side.add(&entry("started", None));
+
+        assert_eq!(side.profile.total_entries, 3);
+        assert_eq!(side.profile.level_counts.get("ERROR"), Some(&2));
+        assert_eq!(side.profile.level_counts.get("NONE"), Some(&1));
+        assert_eq!(side.templates["panic: boom"].count, 2);
+    }
+
+    #[test]
+    fn reports_new_increased_and_vanished_templates() {
+        let baseline = side(&[("ok", 1000), ("retry 1", 10), ("old path used", 5)]);
+        let candidate = side(&[("ok", 1000), ("retry 2", 60), ("cache miss", 4)]);
+
+        let comparison = compare(baseline, candidate, &CompareOptions::default());
+        assert_eq!(
+            changes(&comparison),
+            [
+                (TemplateChangeKind::New, "cache miss"),
+                (TemplateChangeKind::Increased, "retry <num>"),
+                (TemplateChangeKind::Vanished, "old path used"),
+            ]
+        );
+        assert_eq!(comparison.baseline.total_entries, 1015);
+        assert_eq!(comparison.candidate.template_count, 3);
+    }
+
+    #[test]
+    fn ignores_rare_and_insignificant_changes() {
+        // Below min_count on the side where they show up
+        let baseline = side(&[("ok", 100), ("rare", 2)]);
+        let candidate = side(&[("ok", 100), ("seldom", 2)]);
+        assert!(compare(baseline, candidate, &CompareOptions::default())
+            .changes
+            .is_empty());
+
+        // Doubled, but on too few occurrences to rule out chance
+        let baseline = side(&[("ok", 100), ("slow", 3)]);
+        let candidate = side(&[("ok", 100), ("slow", 6)]);
+        assert!(compare(baseline, candidate, &CompareOptions::default())
+            .changes
+            .is_empty());
+    }
+
+    #[test]
+    fn options_override_the_defaults() {
+        let baseline = side(&[("ok", 100), ("rare", 2)]);
+        let candidate = side(&[("ok", 100), ("a", 5), ("b", 9)]);
+        let options = CompareOptions {
+            min_count: Some(1),
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        let comparison = compare(baseline, candidate, &options);
+        // Most frequent first within a kind, then cut to the limit
+        assert_eq!(
+            changes(&comparison),
+            [
+                (TemplateChangeKind::New, "b"),
+                (TemplateChangeKind::New, "a")
+            ]
+        );
+    }
+
+    #[test]
+    fn z_score_of_proportions() {
+        assert_eq!(z_score((0, 0), (5, 10)), 0.0);
+        assert_eq!(z_score((0, 10), (0, 10)), 0.0);
+        assert!(z_score((10, 1000), (60, 1000)) > MIN_Z_SCORE);
+        assert!(z_score((60, 1000), (10, 1000)) < 0.0);
+    }
+}
@@ -0,0 +1,94 @@
+use kube::config::Kubeconfig;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::MissedTickBehavior;
+
+use crate::error::K8sError;
+use crate::k8s::client::{kubeconfig_modified, load_kubeconfig_with_sources};
+use crate::k8s::clusters::cluster_infos;
+use crate::state::AppState;
+
+/// How often the kubeconfig files are checked for changes
+const POLL_INTERVAL_MS: u64 = 2000;
+
+/// Event emitted with the new `ClusterInfo` list after a kubeconfig change
+const CLUSTERS_CHANGED_EVENT: &str = "clusters-changed";
+
+/// Watch the kubeconfig files for edits (e.g. by `aws eks update-kubeconfig`
+/// or `kubectx`). On a change, pooled clients of removed or modified contexts
+/// are dropped and `clusters-changed` is emitted.
+///
+/// Files are polled by modification time rather than through file system
+/// notifications, since tools often replace the file with a rename.
+pub fn spawn_kubeconfig_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let settings = app.state::<AppState>().settings.clone();
+        let mut snapshot = kubeconfig_modified(&settings);
+        let mut fingerprints = load_kubeconfig_with_sources(&settings)
+            .map(|loaded| context_fingerprints(&loaded.kubeconfig))
+            .unwrap_or_default();
+
+        let mut interval = tokio::time::interval(Duration::from_millis(POLL_INTERVAL_MS));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let modified = kubeconfig_modified(&settings);
+            if modified == snapshot {
+                continue;
+            }
+            snapshot = modified;
+
+            let (clusters, next) = match load_kubeconfig_with_sources(&settings) {
+                Ok(loaded) => (
+                    cluster_infos(&loaded),
+                    context_fingerprints(&loaded.kubeconfig),
+                ),
+                // Every kubeconfig file was removed
+                Err(K8sError::NoKubeconfig) => (Vec::new(), HashMap::new()),
+                // Most likely a file caught mid-write, the next write will be picked up
+                Err(_) => continue,
+            };
+
+            let state = app.state::<AppState>();
+            for (context, fingerprint) in &fingerprints {
+                if next.get(context) != Some(fingerprint) {
+                    state.clients.invalidate(context).await;
+                }
+            }
+            fingerprints = next;
+
+            let _ = app.emit(CLUSTERS_CHANGED_EVENT, clusters);
+        }
+    });
+}
+
+/// Serialized context, cluster and user entries per context, so a change to any
+/// of them (server, certificates, credentials, default namespace...) is noticed
+fn context_fingerprints(kubeconfig: &Kubeconfig) -> HashMap<String, serde_json::Value> {
+    kubeconfig
+        .contexts
+        .iter()
+        .map(|named| {
+            let context = named.context.as_ref();
+            let cluster = context.and_then(|c| {
+                kubeconfig
+                    .clusters
+                    .iter()
+                    .find(|cluster| cluster.name == c.cluster)
+            });
+            let user = context
+                .and_then(|c| c.user.as_ref())
+                .and_then(|user| kubeconfig.auth_infos.iter().find(|a| &a.name == user));
+
+            let fingerprint = serde_json::json!({
+                "context": named,
+                "cluster": cluster,
+                "user": user,
+            });
+            (named.name.clone(), fingerprint)
+        })
+        .collect()
+}
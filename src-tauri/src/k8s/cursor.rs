@@ -0,0 +1,206 @@
+//! Cursors for incremental log fetches: where the previous fetch stopped, so
+//! the next one returns only the lines logged since.
+
+use chrono::{DateTime, Utc};
+
+use crate::k8s::logs::extract_timestamp;
+use crate::timestamp;
+
+/// Position after the last line returned: its kubelet timestamp, plus hashes
+/// of every line sharing that timestamp so ties aren't returned twice
+#[derive(Debug, Clone)]
+pub(crate) struct LogCursor {
+    time: DateTime<Utc>,
+    seen: Vec<u64>,
+}
+
+impl LogCursor {
+    /// Decode a cursor handed out by `encode`
+    pub(crate) fn decode(cursor: &str) -> Option<Self> {
+        let (time, hashes) = cursor.split_once('/')?;
+        Some(Self {
+            time: DateTime::parse_from_rfc3339(time).ok()?.to_utc(),
+            seen: hashes
+                .split(',')
+                .filter(|h| !h.is_empty())
+                .map(|h| u64::from_str_radix(h, 16).ok())
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// Opaque string form given to the frontend
+    pub(crate) fn encode(&self) -> String {
+        let hashes: Vec<String> = self.seen.iter().map(|h| format!("{:x}", h)).collect();
+        format!("{}/{}", timestamp::format(self.time), hashes.join(","))
+    }
+
+    /// Lower bound to fetch from. The API truncates it to the second, so the
+    /// lines already seen in that second come back and are dropped by `CursorTracker`.
+    pub(crate) fn since(&self) -> DateTime<Utc> {
+        self.time
+    }
+}
+
+/// Lets through the lines that come after a cursor, tracking the cursor to
+/// pass next time. Without a cursor every line is new.
+pub(crate) struct CursorTracker {
+    after: Option<LogCursor>,
+    /// Hashes at the cursor's time not matched yet: a line logged twice in the
+    /// same instant is only skipped as many times as it was seen
+    unmatched: Vec<u64>,
+    position: Option<LogCursor>,
+}
+
+impl CursorTracker {
+    pub(crate) fn new(after: Option<LogCursor>) -> Self {
+        Self {
+            unmatched: after.as_ref().map(|c| c.seen.clone()).unwrap_or_default(),
+            position: after.clone(),
+            after,
+        }
+    }
+
+    /// Whether `line` comes after the cursor. Lines let through move the
+    /// position forward.
+    pub(crate) fn admit(&mut self, line: &str) -> bool {
+        let Some(time) = extract_timestamp(line)
+            .0
+            .and_then(|ts| timestamp::parse(&ts, None))
+        else {
+            // Can't be placed relative to the cursor, so only the first fetch has it
+            return self.after.is_none();
+        };
+        let hash = fnv1a(line);
+
+        if let Some(after) = &self.after {
+            if time < after.time {
+                return false;
+            }
+            if time == after.time {
+                if let Some(i) = self.unmatched.iter().position(|&h| h == hash) {
+                    self.unmatched.swap_remove(i);
+                    return false;
+                }
+            }
+        }
+
+        match self.position.as_mut() {
+            Some(position) if position.time == time => position.seen.push(hash),
+            _ => {
+                self.position = Some(LogCursor {
+                    time,
+                    seen: vec![hash],
+                })
+            }
+        }
+        true
+    }
+
+    /// Cursor after the last line let through (or the starting one)
+    pub(crate) fn position(&self) -> Option<&LogCursor> {
+        self.position.as_ref()
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `DefaultHasher`
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T1: &str = "2024-10-26T12:00:00.100000000Z";
+    const T2: &str = "2024-10-26T12:00:00.200000000Z";
+
+    fn poll(logs: &[String], cursor: Option<&str>) -> (Vec<String>, Option<String>) {
+        let cursor = cursor.map(|c| LogCursor::decode(c).unwrap());
+        let mut tracker = CursorTracker::new(cursor);
+        let lines = logs.iter().filter(|l| tracker.admit(l)).cloned().collect();
+        (lines, tracker.position().map(LogCursor::encode))
+    }
+
+    fn line(time: &str, text: &str) -> String {
+        format!("{} {}", time, text)
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let cursor = LogCursor {
+            time: DateTime::parse_from_rfc3339(T1).unwrap().to_utc(),
+            seen: vec![0, 1, u64::MAX, fnv1a("a line")],
+        };
+        let encoded = cursor.encode();
+        let decoded = LogCursor::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.time, cursor.time);
+        assert_eq!(decoded.seen, cursor.seen);
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    fn decode_rejects_malformed_cursors() {
+        assert!(LogCursor::decode("").is_none());
+        assert!(LogCursor::decode("not a time/1f").is_none());
+        assert!(LogCursor::decode(&format!("{}/xyz", T1)).is_none());
+        assert!(LogCursor::decode(&format!("{}/", T1)).is_some());
+    }
+
+    #[test]
+    fn returns_only_lines_after_the_cursor() {
+        let first = vec![line(T1, "a"), line(T1, "b")];
+        let (lines, cursor) = poll(&first, None);
+        assert_eq!(lines, first);
+
+        // The next fetch starts at the same second, so it repeats what was seen
+        let second = vec![line(T1, "a"), line(T1, "b"), line(T1, "c"), line(T2, "d")];
+        let (lines, cursor) = poll(&second, cursor.as_deref());
+        assert_eq!(lines, [line(T1, "c"), line(T2, "d")]);
+
+        let (lines, next) = poll(&second, cursor.as_deref());
+        assert!(lines.is_empty());
+        assert_eq!(next, cursor);
+    }
+
+    #[test]
+    fn identical_lines_in_the_same_instant_are_counted() {
+        // Same text and time, so the same hash
+        let (_, cursor) = poll(&[line(T1, "retry")], None);
+
+        let logs = vec![line(T1, "retry"), line(T1, "retry")];
+        let (lines, cursor) = poll(&logs, cursor.as_deref());
+        assert_eq!(lines, [line(T1, "retry")]);
+
+        let (lines, _) = poll(&logs, cursor.as_deref());
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn tracker_resumes_after_its_position() {
+        let mut tracker = CursorTracker::new(None);
+        assert!(tracker.admit(&line(T1, "a")));
+        assert!(tracker.admit(&line(T1, "b")));
+        let position = tracker.position().cloned();
+
+        // The resumed stream starts over at the second of the last line
+        let mut resumed = CursorTracker::new(position);
+        let lines = [line(T1, "a"), line(T1, "b"), line(T2, "c")];
+        let admitted: Vec<bool> = lines.iter().map(|l| resumed.admit(l)).collect();
+        assert_eq!(admitted, [false, false, true]);
+        let t2 = DateTime::parse_from_rfc3339(T2).unwrap().to_utc();
+        assert_eq!(resumed.position().unwrap().since(), t2);
+    }
+
+    #[test]
+    fn lines_without_timestamp_only_come_with_the_first_fetch() {
+        let logs = vec![line(T1, "a"), "continued".to_string()];
+        let (lines, cursor) = poll(&logs, None);
+        assert_eq!(lines, logs);
+
+        let (lines, _) = poll(&logs, cursor.as_deref());
+        assert!(lines.is_empty());
+    }
+}
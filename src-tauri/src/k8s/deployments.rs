@@ -1,22 +1,32 @@
 use std::collections::BTreeMap;
 
-use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
 use kube::api::ListParams;
-use kube::Api;
+use kube::{Api, Client};
+use tauri::State;
 
-use crate::error::K8sError;
-use crate::k8s::client::get_client_for_context;
-use crate::models::{DeploymentInfo, DeploymentDetails, DeploymentCondition};
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::selector::to_selector_string;
+use crate::models::{DeploymentCondition, DeploymentDetails, DeploymentInfo, DeploymentRevision};
+use crate::state::AppState;
+
+/// Revision number the deployment controller stamps on a deployment and its ReplicaSets
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+const CHANGE_CAUSE_ANNOTATION: &str = "kubernetes.io/change-cause";
 
 #[tauri::command]
 pub async fn get_deployments(
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
 ) -> Result<Vec<DeploymentInfo>, K8sError> {
-    let client = get_client_for_context(&context).await?;
+    let client = state.clients.get(&context).await?;
     let deployments: Api<Deployment> = Api::namespaced(client, &namespace);
 
-    let deploy_list = deployments.list(&ListParams::default()).await?;
+    let deploy_list = deployments
+        .list(&ListParams::default())
+        .await
+        .in_scope(&ErrorScope::namespaced(&context, &namespace))?;
 
     let result: Vec<DeploymentInfo> = deploy_list
         .items
@@ -47,14 +57,17 @@ pub async fn get_deployments(
 
 #[tauri::command]
 pub async fn get_deployment_details(
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
     deployment_name: String,
 ) -> Result<DeploymentDetails, K8sError> {
-    let client = get_client_for_context(&context).await?;
-    let deployments: Api<Deployment> = Api::namespaced(client, &namespace);
+    let client = state.clients.get(&context).await?;
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), &namespace);
 
-    let deployment = deployments.get(&deployment_name).await?;
+    let scope =
+        ErrorScope::namespaced(&context, &namespace).resource("deployments", &deployment_name);
+    let deployment = deployments.get(&deployment_name).await.in_scope(&scope)?;
 
     let metadata = &deployment.metadata;
     let spec = deployment.spec.as_ref();
@@ -81,6 +94,11 @@ pub async fn get_deployment_details(
     let selector: BTreeMap<String, String> = spec
         .and_then(|s| s.selector.match_labels.clone())
         .unwrap_or_default();
+    let selector_string = spec
+        .map(|s| to_selector_string(&s.selector))
+        .transpose()
+        .in_scope(&scope)?
+        .unwrap_or_default();
 
     let available_replicas = status.and_then(|s| s.available_replicas).unwrap_or(0);
     let ready_replicas = status.and_then(|s| s.ready_replicas).unwrap_or(0);
@@ -103,6 +121,14 @@ pub async fn get_deployment_details(
         })
         .unwrap_or_default();
 
+    let current_revision = revision_of(metadata.annotations.as_ref());
+    let revisions = owned_replica_sets(&client, &namespace, &deployment)
+        .await
+        .in_scope(&scope)?
+        .iter()
+        .map(|rs| to_revision(rs, current_revision))
+        .collect();
+
     Ok(DeploymentDetails {
         name,
         namespace,
@@ -117,6 +143,107 @@ pub async fn get_deployment_details(
         labels,
         annotations,
         selector,
+        selector_string,
         conditions,
+        revisions,
     })
 }
+
+/// ReplicaSets owned by a deployment, newest revision first
+pub(crate) async fn owned_replica_sets(
+    client: &Client,
+    namespace: &str,
+    deployment: &Deployment,
+) -> Result<Vec<ReplicaSet>, K8sError> {
+    let uid = deployment.metadata.uid.clone().unwrap_or_default();
+
+    // A deployment's ReplicaSets carry its pod template labels, so its selector
+    // narrows the list; ownership is what decides
+    let mut params = ListParams::default();
+    if let Some(spec) = &deployment.spec {
+        params = params.labels(&to_selector_string(&spec.selector)?);
+    }
+
+    let replica_sets: Api<ReplicaSet> = Api::namespaced(client.clone(), namespace);
+    let mut owned: Vec<ReplicaSet> = replica_sets
+        .list(&params)
+        .await?
+        .items
+        .into_iter()
+        .filter(|rs| {
+            rs.metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .any(|owner| owner.controller == Some(true) && owner.uid == uid)
+        })
+        .collect();
+
+    owned.sort_by_key(|rs| std::cmp::Reverse(revision_of(rs.metadata.annotations.as_ref())));
+    Ok(owned)
+}
+
+/// The ReplicaSet holding a given revision of a deployment
+pub(crate) async fn revision_replica_set(
+    client: &Client,
+    namespace: &str,
+    deployment_name: &str,
+    revision: i64,
+) -> Result<ReplicaSet, K8sError> {
+    let deployment: Deployment = Api::namespaced(client.clone(), namespace)
+        .get(deployment_name)
+        .await?;
+
+    owned_replica_sets(client, namespace, &deployment)
+        .await?
+        .into_iter()
+        .find(|rs| revision_of(rs.metadata.annotations.as_ref()) == revision)
+        .ok_or_else(|| K8sError::RevisionNotFound {
+            deployment: deployment_name.to_string(),
+            revision,
+        })
+}
+
+fn revision_of(annotations: Option<&BTreeMap<String, String>>) -> i64 {
+    annotations
+        .and_then(|a| a.get(REVISION_ANNOTATION))
+        .and_then(|r| r.parse().ok())
+        .unwrap_or(0)
+}
+
+fn to_revision(rs: &ReplicaSet, current_revision: i64) -> DeploymentRevision {
+    let annotations = rs.metadata.annotations.as_ref();
+    let revision = revision_of(annotations);
+    let status = rs.status.as_ref();
+
+    let images = rs
+        .spec
+        .as_ref()
+        .and_then(|s| s.template.as_ref())
+        .and_then(|t| t.spec.as_ref())
+        .map(|spec| {
+            spec.containers
+                .iter()
+                .filter_map(|c| c.image.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    DeploymentRevision {
+        revision,
+        replica_set: rs.metadata.name.clone().unwrap_or_default(),
+        images,
+        creation_timestamp: rs
+            .metadata
+            .creation_timestamp
+            .as_ref()
+            .map(|t| t.0.to_rfc3339()),
+        change_cause: annotations
+            .and_then(|a| a.get(CHANGE_CAUSE_ANNOTATION))
+            .cloned(),
+        replicas: status.map(|s| s.replicas).unwrap_or(0),
+        ready_replicas: status.and_then(|s| s.ready_replicas).unwrap_or(0),
+        available_replicas: status.and_then(|s| s.available_replicas).unwrap_or(0),
+        current: revision != 0 && revision == current_revision,
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use futures::StreamExt;
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::Event;
+use kube::api::ListParams;
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::{Api, Client};
+use tauri::ipc::Channel;
+use tauri::State;
+
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::models::{
+    EventStreamEvent, EventTarget, KubeEvent, LogEntry, LogSource, WellKnownFields,
+};
+use crate::state::AppState;
+use crate::timestamp;
+
+/// List the events of a pod, a deployment or a whole namespace, oldest first
+#[tauri::command]
+pub async fn get_events(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    target: EventTarget,
+) -> Result<Vec<KubeEvent>, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = target_scope(&context, &namespace, &target);
+
+    let mut filter = EventFilter::new(&client, &namespace, target)
+        .await
+        .in_scope(&scope)?;
+    list_events(&mut filter).await.in_scope(&scope)
+}
+
+/// Follow the events of a pod, a deployment or a whole namespace, pushing the
+/// existing ones then each new occurrence to `on_event` until
+/// `stop_event_stream` is called with the same id.
+#[tauri::command]
+pub async fn start_event_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+    context: String,
+    namespace: String,
+    target: EventTarget,
+    on_event: Channel<EventStreamEvent>,
+) -> Result<(), K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = target_scope(&context, &namespace, &target);
+
+    let mut filter = EventFilter::new(&client, &namespace, target)
+        .await
+        .in_scope(&scope)?;
+    let initial = list_events(&mut filter).await.in_scope(&scope)?;
+
+    let handle = tokio::spawn(async move {
+        if on_event.send(EventStreamEvent::Initial(initial)).is_err() {
+            return;
+        }
+        follow_events(filter, |result| {
+            let message = match result {
+                Ok(event) => EventStreamEvent::Event(event),
+                Err(e) => EventStreamEvent::Error(e.with_scope(&scope).into()),
+            };
+            on_event.send(message).is_ok()
+        })
+        .await;
+        let _ = on_event.send(EventStreamEvent::Ended);
+    });
+
+    state.tasks.track(&stream_id, handle);
+
+    Ok(())
+}
+
+/// Stop a stream started with `start_event_stream`
+#[tauri::command]
+pub async fn stop_event_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+) -> Result<bool, K8sError> {
+    Ok(state.tasks.cancel(&stream_id))
+}
+
+fn target_scope(context: &str, namespace: &str, target: &EventTarget) -> ErrorScope {
+    let scope = ErrorScope::namespaced(context, namespace);
+    match target {
+        EventTarget::Pod { name } => scope.resource("pods", name),
+        EventTarget::Deployment { name } => scope.resource("deployments", name),
+        EventTarget::Namespace => scope,
+    }
+}
+
+/// Decides which events of the namespace concern the target, and remembers
+/// which of them were already handed out
+pub(crate) struct EventFilter {
+    events: Api<Event>,
+    target: EventTarget,
+    /// Resource version of each event already delivered, by uid. An event is
+    /// updated in place (e.g. its count) each time it recurs.
+    delivered: HashMap<String, String>,
+}
+
+impl EventFilter {
+    pub(crate) async fn new(
+        client: &Client,
+        namespace: &str,
+        target: EventTarget,
+    ) -> Result<Self, K8sError> {
+        // Report a missing deployment up front rather than an empty feed
+        if let EventTarget::Deployment { name } = &target {
+            Api::<Deployment>::namespaced(client.clone(), namespace)
+                .get_metadata(name)
+                .await?;
+        }
+
+        Ok(Self {
+            events: Api::namespaced(client.clone(), namespace),
+            target,
+            delivered: HashMap::new(),
+        })
+    }
+
+    /// Field selector narrowing the events listed or watched server-side
+    fn field_selector(&self) -> Option<String> {
+        match &self.target {
+            EventTarget::Pod { name } => Some(format!(
+                "involvedObject.kind=Pod,involvedObject.name={}",
+                name
+            )),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        let kind = event.involved_object.kind.as_deref().unwrap_or_default();
+        let name = event.involved_object.name.as_deref().unwrap_or_default();
+
+        match &self.target {
+            EventTarget::Namespace => true,
+            EventTarget::Pod { name: pod } => kind == "Pod" && name == pod,
+            EventTarget::Deployment { name: deployment } => match kind {
+                "Deployment" => name == deployment,
+                "Pod" | "ReplicaSet" => is_member(deployment, kind, name),
+                _ => false,
+            },
+        }
+    }
+
+    /// Record `event` as delivered. False if this version of it already was.
+    fn deliver(&mut self, event: &Event) -> bool {
+        let (Some(uid), Some(version)) = (&event.metadata.uid, &event.metadata.resource_version)
+        else {
+            return true;
+        };
+        self.delivered.insert(uid.clone(), version.clone()).as_ref() != Some(version)
+    }
+}
+
+/// Whether a pod or ReplicaSet belongs to a deployment, going by its name:
+/// `<deployment>-<hash>` for a ReplicaSet and `<deployment>-<hash>-<suffix>`
+/// for a pod. Events outlive their objects, so the name is all there may be
+/// left to go by.
+fn is_member(deployment: &str, kind: &str, name: &str) -> bool {
+    let Some(rest) = name
+        .strip_prefix(deployment)
+        .and_then(|rest| rest.strip_prefix('-'))
+    else {
+        return false;
+    };
+
+    // Generated parts are short lowercase alphanumerics
+    let generated = |part: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&part.len())
+            && part
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+    };
+    match (kind, rest.split_once('-')) {
+        ("ReplicaSet", None) => generated(rest, 1..=10),
+        ("Pod", Some((hash, suffix))) => generated(hash, 1..=10) && generated(suffix, 5..=5),
+        _ => false,
+    }
+}
+
+/// Events to interleave with a target's logs: those that already happened,
+/// and the filter to follow new ones with
+pub(crate) struct EventFeed {
+    pub(crate) history: Vec<KubeEvent>,
+    pub(crate) filter: EventFilter,
+}
+
+impl EventFeed {
+    pub(crate) async fn load(
+        client: &Client,
+        namespace: &str,
+        target: EventTarget,
+    ) -> Result<Self, K8sError> {
+        let mut filter = EventFilter::new(client, namespace, target).await?;
+        let history = list_events(&mut filter).await?;
+        Ok(Self { history, filter })
+    }
+}
+
+/// Merge event entries (oldest first) into log entries by `timestamp_utc`,
+/// keeping the order of the log entries as it is
+pub(crate) fn interleave(entries: Vec<LogEntry>, events: Vec<LogEntry>) -> Vec<LogEntry> {
+    let mut merged = Vec::with_capacity(entries.len() + events.len());
+    let mut events = events.into_iter().peekable();
+
+    for entry in entries {
+        if entry.timestamp_utc.is_some() {
+            while let Some(event) = events.next_if(|e| e.timestamp_utc <= entry.timestamp_utc) {
+                merged.push(event);
+            }
+        }
+        merged.push(entry);
+    }
+
+    merged.extend(events);
+    merged
+}
+
+/// List the target's events, oldest first
+pub(crate) async fn list_events(filter: &mut EventFilter) -> Result<Vec<KubeEvent>, K8sError> {
+    let mut params = ListParams::default();
+    if let Some(fields) = filter.field_selector() {
+        params = params.fields(&fields);
+    }
+
+    let mut events = Vec::new();
+    for event in filter.events.list(&params).await?.items {
+        if filter.matches(&event) {
+            filter.deliver(&event);
+            events.push(to_kube_event(&event));
+        }
+    }
+
+    events.sort_by(|a, b| a.timestamp_utc.cmp(&b.timestamp_utc));
+    Ok(events)
+}
+
+/// Watch the target's events, calling `send` with each new occurrence (or a
+/// watch error) until it returns false. Events already delivered by
+/// `list_events` or earlier in the watch are skipped, including when the
+/// watcher lists them again on start or after a desync; those it lists that
+/// are new or updated since are sent.
+pub(crate) async fn follow_events<F>(mut filter: EventFilter, mut send: F)
+where
+    F: FnMut(Result<KubeEvent, K8sError>) -> bool,
+{
+    let mut config = watcher::Config::default();
+    if let Some(fields) = filter.field_selector() {
+        config = config.fields(&fields);
+    }
+
+    let mut stream = watcher(filter.events.clone(), config)
+        .default_backoff()
+        .boxed();
+
+    // Uids seen by the current (re-)list, to forget events deleted meanwhile
+    let mut listed = HashSet::new();
+
+    while let Some(event) = stream.next().await {
+        let keep_going = match event {
+            Ok(watcher::Event::Apply(event)) => forward(&mut filter, &event, &mut send),
+            Ok(watcher::Event::InitApply(event)) => {
+                listed.extend(event.metadata.uid.clone());
+                forward(&mut filter, &event, &mut send)
+            }
+            Ok(watcher::Event::Delete(event)) => {
+                if let Some(uid) = &event.metadata.uid {
+                    filter.delivered.remove(uid);
+                }
+                true
+            }
+            Ok(watcher::Event::Init) => {
+                listed.clear();
+                true
+            }
+            Ok(watcher::Event::InitDone) => {
+                filter.delivered.retain(|uid, _| listed.contains(uid));
+                listed.clear();
+                true
+            }
+            // The watcher backs off and retries on its own
+            Err(e) => send(Err(e.into())),
+        };
+        if !keep_going {
+            return;
+        }
+    }
+}
+
+/// Send an event of the target unless this version of it was already sent.
+/// Returns false once `send` does.
+fn forward<F>(filter: &mut EventFilter, event: &Event, send: &mut F) -> bool
+where
+    F: FnMut(Result<KubeEvent, K8sError>) -> bool,
+{
+    if !filter.matches(event) || !filter.deliver(event) {
+        return true;
+    }
+    send(Ok(to_kube_event(event)))
+}
+
+fn to_kube_event(event: &Event) -> KubeEvent {
+    let first_timestamp = event.first_timestamp.as_ref().map(|t| t.0);
+    let last_timestamp = event.last_timestamp.as_ref().map(|t| t.0);
+
+    // Events from the events.k8s.io API only set `eventTime`
+    let time = last_timestamp
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or(first_timestamp)
+        .or_else(|| event.metadata.creation_timestamp.as_ref().map(|t| t.0));
+
+    KubeEvent {
+        event_type: event.type_.clone().unwrap_or_else(|| "Normal".to_string()),
+        reason: event.reason.clone(),
+        message: event.message.clone(),
+        object_kind: event.involved_object.kind.clone(),
+        object_name: event.involved_object.name.clone(),
+        count: event.count,
+        first_timestamp: first_timestamp.map(|t| t.to_rfc3339()),
+        last_timestamp: last_timestamp.map(|t| t.to_rfc3339()),
+        timestamp_utc: time.map(timestamp::format),
+        component: event
+            .source
+            .as_ref()
+            .and_then(|s| s.component.clone())
+            .or_else(|| event.reporting_component.clone())
+            .filter(|c| !c.is_empty()),
+    }
+}
+
+/// A synthetic log entry standing for an event, to interleave with log lines
+pub(crate) fn event_entry(event: KubeEvent) -> LogEntry {
+    let message = match (&event.reason, &event.message) {
+        (Some(reason), Some(message)) => format!("{}: {}", reason, message.trim_end()),
+        (Some(text), None) | (None, Some(text)) => text.clone(),
+        (None, None) => String::new(),
+    };
+
+    let mut fields = BTreeMap::new();
+    fields.insert("event.type".to_string(), event.event_type.clone());
+    let optional = [
+        ("event.reason", event.reason),
+        ("event.object_kind", event.object_kind),
+        ("event.object_name", event.object_name.clone()),
+        ("event.count", event.count.map(|c| c.to_string())),
+        ("event.component", event.component),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), value);
+        }
+    }
+
+    LogEntry {
+        timestamp: event
+            .last_timestamp
+            .or(event.first_timestamp)
+            .or_else(|| event.timestamp_utc.clone()),
+        timestamp_utc: event.timestamp_utc,
+        level: Some(
+            if event.event_type == "Warning" {
+                "WARN"
+            } else {
+                "INFO"
+            }
+            .to_string(),
+        ),
+        raw: message.clone(),
+        message,
+        is_json: false,
+        pod_name: event.object_name.unwrap_or_default(),
+        container_name: String::new(),
+        source: LogSource::Event,
+        fields,
+        well_known: WellKnownFields::default(),
+        spans: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(message: &str, time: Option<&str>) -> LogEntry {
+        LogEntry {
+            timestamp_utc: time.map(str::to_string),
+            ..LogEntry::from_message(message)
+        }
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    fn event(reason: Option<&str>, message: Option<&str>) -> KubeEvent {
+        KubeEvent {
+            event_type: "Warning".to_string(),
+            reason: reason.map(str::to_string),
+            message: message.map(str::to_string),
+            object_kind: Some("Pod".to_string()),
+            object_name: Some("api-7d9f8-x4k2p".to_string()),
+            count: Some(3),
+            first_timestamp: Some("2024-10-26T11:00:00Z".to_string()),
+            last_timestamp: Some("2024-10-26T12:00:00Z".to_string()),
+            timestamp_utc: Some("2024-10-26T12:00:00.000000000Z".to_string()),
+            component: Some("kubelet".to_string()),
+        }
+    }
+
+    #[test]
+    fn interleaves_events_by_time() {
+        let entries = vec![
+            at("log 1", Some("2024-10-26T12:00:01.000000000Z")),
+            at("continued", None),
+            at("log 2", Some("2024-10-26T12:00:03.000000000Z")),
+        ];
+        let events = vec![
+            at("event 0", Some("2024-10-26T12:00:00.000000000Z")),
+            at("event 2", Some("2024-10-26T12:00:02.000000000Z")),
+            at("event 3", Some("2024-10-26T12:00:03.000000000Z")),
+            at("event 4", Some("2024-10-26T12:00:04.000000000Z")),
+        ];
+
+        assert_eq!(
+            messages(&interleave(entries, events)),
+            [
+                "event 0",
+                "log 1",
+                "continued",
+                "event 2",
+                "event 3",
+                "log 2",
+                "event 4"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_log_order_when_there_are_no_events() {
+        let entries = vec![
+            at("b", Some("2024-10-26T12:00:02.000000000Z")),
+            at("a", Some("2024-10-26T12:00:01.000000000Z")),
+        ];
+        assert_eq!(messages(&interleave(entries, vec![])), ["b", "a"]);
+        assert_eq!(messages(&interleave(vec![], vec![at("e", None)])), ["e"]);
+    }
+
+    #[test]
+    fn deployment_members_are_matched_by_name() {
+        assert!(is_member("api", "ReplicaSet", "api-7d9f8b6c4"));
+        assert!(is_member("api", "Pod", "api-7d9f8b6c4-x4k2p"));
+
+        // Another deployment sharing the prefix
+        assert!(!is_member("api", "ReplicaSet", "api-gateway-7d9f8"));
+        assert!(!is_member("api", "Pod", "api-gateway-7d9f8-x4k2p"));
+        assert!(!is_member("api", "Pod", "api-7d9f8-X4K2P"));
+        assert!(!is_member("api", "Pod", "api-7d9f8"));
+        assert!(!is_member("api", "ReplicaSet", "api"));
+        assert!(!is_member("api", "Service", "api-7d9f8"));
+    }
+
+    #[test]
+    fn events_become_entries() {
+        let entry = event_entry(event(Some("BackOff"), Some("Back-off restarting\n")));
+        assert_eq!(entry.source, LogSource::Event);
+        assert_eq!(entry.message, "BackOff: Back-off restarting");
+        assert_eq!(entry.level.as_deref(), Some("WARN"));
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-10-26T12:00:00Z"));
+        assert_eq!(entry.pod_name, "api-7d9f8-x4k2p");
+        assert_eq!(entry.fields["event.count"], "3");
+        assert_eq!(entry.fields["event.component"], "kubelet");
+
+        let normal = KubeEvent {
+            event_type: "Normal".to_string(),
+            ..event(None, Some("Pulled image"))
+        };
+        let entry = event_entry(normal);
+        assert_eq!(entry.message, "Pulled image");
+        assert_eq!(entry.level.as_deref(), Some("INFO"));
+        assert!(!entry.fields.contains_key("event.reason"));
+    }
+}
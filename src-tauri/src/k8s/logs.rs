@@ -1,27 +1,194 @@
-use k8s_openapi::api::apps::v1::Deployment;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::{AsyncBufReadExt, StreamExt};
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::{ListParams, LogParams};
 use kube::Api;
+use regex::Regex;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, State};
 
-use crate::error::K8sError;
-use crate::k8s::client::get_client_for_context;
-use crate::models::{LogEntry, LogSearchResult};
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::cursor::{CursorTracker, LogCursor};
+use crate::k8s::deployments::revision_replica_set;
+use crate::k8s::events::{event_entry, interleave, list_events, EventFilter};
+use crate::k8s::pods::{has_previous_instance, is_running, list_containers};
+use crate::k8s::selector::require_selector;
+use crate::k8s::workloads::list_workload_pods;
+use crate::models::{
+    ContainerSource, EventTarget, LogEntry, LogPage, LogSearchFailure, LogSearchOptions,
+    LogSearchProgress, LogSearchResponse, LogSearchResult, LogSource, MultilineOptions,
+    PodLogOptions, StyledSpan, WellKnownFields, WorkloadKind,
+};
+use crate::parser::{LineParser, ParseConfig};
+use crate::query::Query;
+use crate::state::AppState;
+use crate::{ansi, formats, logfmt, timestamp};
+
+/// Number of containers searched concurrently when the caller doesn't say
+pub(crate) const DEFAULT_SEARCH_PARALLELISM: usize = 8;
+
+/// Maximum number of lines fetched per container when searching
+const SEARCH_TAIL_LINES: i64 = 1000;
+
+/// Event emitted as each container of a search completes
+const SEARCH_PROGRESS_EVENT: &str = "log-search-progress";
 
 #[tauri::command]
 pub async fn get_pod_logs(
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
     pod_name: String,
-    container: Option<String>,
-    since_seconds: Option<i64>,
-    tail_lines: Option<i64>,
+    options: PodLogOptions,
 ) -> Result<Vec<LogEntry>, K8sError> {
-    let client = get_client_for_context(&context).await?;
-    let pods: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+    let include_events = options.include_events.unwrap_or(false);
+    let window = TimeWindow::parse(
+        options.since_seconds,
+        options.since_time.as_deref(),
+        options.until_time.as_deref(),
+    )?;
+    // A relative range starts now
+    let since = window.since.or_else(|| {
+        options
+            .since_seconds
+            .map(|seconds| Utc::now() - TimeDelta::seconds(seconds))
+    });
+
+    let fetched = fetch_pod_logs(&state, &context, &namespace, &pod_name, options, None).await?;
+    let entries = parse_log_text(&fetched.logs, &fetched.parser);
+
+    if !include_events {
+        return Ok(entries);
+    }
+
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("pods", &pod_name);
+    let target = EventTarget::Pod { name: pod_name };
+    let mut filter = EventFilter::new(&client, &namespace, target)
+        .await
+        .in_scope(&scope)?;
+
+    let in_range = |time: &DateTime<Utc>| {
+        since.is_none_or(|since| *time >= since) && window.until.is_none_or(|until| *time <= until)
+    };
+    let events = list_events(&mut filter)
+        .await
+        .in_scope(&scope)?
+        .into_iter()
+        .filter(|event| {
+            event
+                .timestamp_utc
+                .as_deref()
+                .and_then(|ts| timestamp::parse(ts, None))
+                .is_some_and(|time| in_range(&time))
+        })
+        .map(event_entry)
+        .collect();
+
+    Ok(interleave(entries, events))
+}
+
+/// Fetch the lines logged since the previous call, for polling without
+/// duplicates or gaps. The first call (without a cursor) honors the range and
+/// tail of `options`; later ones return everything after the cursor.
+///
+/// The last entry of a running container may still get continuation lines, so
+/// it is held back and the cursor left before it, until a later call shows
+/// where it ends.
+#[tauri::command]
+pub async fn poll_pod_logs(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    pod_name: String,
+    options: PodLogOptions,
+    cursor: Option<String>,
+) -> Result<LogPage, K8sError> {
+    let cursor = cursor
+        .map(|c| LogCursor::decode(&c).ok_or(K8sError::InvalidCursor(c)))
+        .transpose()?;
+
+    let fetched = fetch_pod_logs(
+        &state,
+        &context,
+        &namespace,
+        &pod_name,
+        options,
+        cursor.as_ref(),
+    )
+    .await?;
+    let (entries, next) = parse_page(&fetched, cursor);
+
+    Ok(LogPage {
+        entries,
+        // Nothing complete yet: start from scratch next time too
+        cursor: next.as_ref().map(LogCursor::encode),
+    })
+}
+
+/// Group the lines after `cursor` into entries, returning the complete ones
+/// and the cursor after the last of them
+fn parse_page(
+    fetched: &FetchedLogs,
+    cursor: Option<LogCursor>,
+) -> (Vec<LogEntry>, Option<LogCursor>) {
+    let mut tracker = CursorTracker::new(cursor);
+    let mut grouper = MultilineGrouper::new(&fetched.parser.multiline);
+    let mut entries = Vec::new();
+    // Position after the last line of the last complete entry
+    let mut complete = tracker.position().cloned();
+
+    for line in fetched.logs.lines() {
+        let had_pending = grouper.has_pending();
+        let before = tracker.position().cloned();
+        if !tracker.admit(line) {
+            continue;
+        }
+
+        let done = grouper.push(parse_log_line(line, &fetched.parser));
+        if !grouper.has_pending() {
+            // Grouping is off, so the line is an entry of its own
+            complete = tracker.position().cloned();
+        } else if done.is_some() || !had_pending {
+            // The line starts a new entry
+            complete = before;
+        }
+        entries.extend(done);
+    }
+
+    if fetched.ended {
+        entries.extend(grouper.flush());
+        complete = tracker.position().cloned();
+    }
+    (entries, complete)
+}
+
+/// Raw logs of a pod's container, with what it takes to parse them
+struct FetchedLogs {
+    logs: String,
+    parser: LineParser,
+    /// The container instance read has terminated, so no more lines follow
+    ended: bool,
+}
+
+/// Fetch the raw logs of a pod's container
+async fn fetch_pod_logs(
+    state: &AppState,
+    context: &str,
+    namespace: &str,
+    pod_name: &str,
+    options: PodLogOptions,
+    cursor: Option<&LogCursor>,
+) -> Result<FetchedLogs, K8sError> {
+    let client = state.clients.get(context).await?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+
+    let scope = ErrorScope::namespaced(context, namespace).resource("pods", pod_name);
 
     // Get the pod to find container name if not specified
-    let pod = pods.get(&pod_name).await?;
-    let container_name = container.or_else(|| {
+    let pod = pods.get(pod_name).await.in_scope(&scope)?;
+    let container_name = options.container.or_else(|| {
         pod.spec
             .as_ref()
             .and_then(|s| s.containers.first())
@@ -30,6 +197,8 @@ pub async fn get_pod_logs(
 
     let mut params = LogParams {
         timestamps: true,
+        // Logs of the prior (e.g. crashed) instance of the container
+        previous: options.previous.unwrap_or(false),
         ..Default::default()
     };
 
@@ -37,144 +206,479 @@ pub async fn get_pod_logs(
         params.container = Some(c.clone());
     }
 
-    if let Some(since) = since_seconds {
-        params.since_seconds = Some(since);
-    }
-
-    if let Some(tail) = tail_lines {
-        params.tail_lines = Some(tail);
-    }
+    let window = match cursor {
+        // Everything after the cursor, whatever the requested range
+        Some(cursor) => TimeWindow {
+            since: Some(cursor.since()),
+            until: None,
+        },
+        None => {
+            params.since_seconds = options.since_seconds;
+            params.tail_lines = options.tail_lines;
+            TimeWindow::parse(
+                options.since_seconds,
+                options.since_time.as_deref(),
+                options.until_time.as_deref(),
+            )?
+        }
+    };
 
-    let logs = pods.logs(&pod_name, &params).await?;
     let container_name = container_name.unwrap_or_default();
+    let config = ParseConfig::load(&state.settings, options.multiline.as_ref())?;
+    let parser = config.parser_for(&pod, &container_name);
+    let ended = params.previous || !is_running(&pod, &container_name);
+    let logs = fetch_logs(&pods, pod_name, params, window)
+        .await
+        .in_scope(&scope)?;
 
-    let entries: Vec<LogEntry> = logs
-        .lines()
-        .map(|line| parse_log_line(line, &pod_name, &container_name))
-        .collect();
-
-    Ok(entries)
+    Ok(FetchedLogs {
+        logs,
+        parser,
+        ended,
+    })
 }
 
 #[tauri::command]
 pub async fn search_deployment_logs(
+    app: AppHandle,
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
     deployment: String,
-    keyword: Option<String>,
-    log_level: Option<String>,
-    since_seconds: Option<i64>,
-) -> Result<Vec<LogSearchResult>, K8sError> {
-    let client = get_client_for_context(&context).await?;
-
-    // Get the deployment to find its label selector
-    let deployments: Api<Deployment> = Api::namespaced(client.clone(), &namespace);
-    let deploy = deployments.get(&deployment).await?;
-
-    let selector = deploy
-        .spec
-        .as_ref()
-        .and_then(|s| s.selector.match_labels.as_ref())
-        .map(|labels| {
-            labels
-                .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
-                .collect::<Vec<_>>()
-                .join(",")
+    options: LogSearchOptions,
+) -> Result<LogSearchResponse, K8sError> {
+    search_workload_logs(
+        app,
+        state,
+        context,
+        namespace,
+        WorkloadKind::Deployment,
+        deployment,
+        options,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn search_workload_logs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    kind: WorkloadKind,
+    name: String,
+    options: LogSearchOptions,
+) -> Result<LogSearchResponse, K8sError> {
+    let client = state.clients.get(&context).await?;
+
+    // Get all pods for this workload
+    let scope = ErrorScope::namespaced(&context, &namespace).resource(kind.resource(), &name);
+    let pod_list = list_workload_pods(&client, &namespace, kind, &name)
+        .await
+        .in_scope(&scope)?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+    search_pods(app, &state, pods, pod_list, options).await
+}
+
+/// Search the logs of the pods of one rollout revision of a deployment, e.g.
+/// to tell whether errors started with the new version
+#[tauri::command]
+pub async fn search_revision_logs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    deployment: String,
+    revision: i64,
+    options: LogSearchOptions,
+) -> Result<LogSearchResponse, K8sError> {
+    let client = state.clients.get(&context).await?;
+
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("deployments", &deployment);
+    let replica_set = revision_replica_set(&client, &namespace, &deployment, revision)
+        .await
+        .in_scope(&scope)?;
+    // The ReplicaSet's selector includes its pod-template-hash, so it only
+    // matches pods of that revision
+    let name = replica_set.metadata.name.unwrap_or_default();
+    let pod_list = list_workload_pods(&client, &namespace, WorkloadKind::ReplicaSet, &name)
+        .await
+        .in_scope(&scope)?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+    search_pods(app, &state, pods, pod_list, options).await
+}
+
+/// Search the logs of all pods matching a free-form label selector such as
+/// `app=api,tier in (web,worker)`
+#[tauri::command]
+pub async fn search_selector_logs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    selector: String,
+    options: LogSearchOptions,
+) -> Result<LogSearchResponse, K8sError> {
+    require_selector(&selector)?;
+    let client = state.clients.get(&context).await?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+    let pod_list = pods
+        .list(&ListParams::default().labels(&selector))
+        .await
+        .in_scope(&ErrorScope::namespaced(&context, &namespace))?
+        .items;
+
+    search_pods(app, &state, pods, pod_list, options).await
+}
+
+/// Search every container of the given pods
+async fn search_pods(
+    app: AppHandle,
+    state: &AppState,
+    pods: Api<Pod>,
+    pod_list: Vec<Pod>,
+    options: LogSearchOptions,
+) -> Result<LogSearchResponse, K8sError> {
+    // Compile the query once up front so syntax errors fail the whole search
+    let query = options.query.as_deref().map(Query::parse).transpose()?;
+    let window = TimeWindow::parse(
+        options.since_seconds,
+        options.since_time.as_deref(),
+        options.until_time.as_deref(),
+    )?;
+    let config = ParseConfig::load(&state.settings, options.multiline.as_ref())?;
+
+    let previous = options.previous.unwrap_or(false);
+    let targets: Vec<(LineParser, ContainerSource)> = pod_list
+        .iter()
+        .flat_map(|pod| {
+            let config = &config;
+            list_containers(pod)
+                .into_iter()
+                // Containers that never restarted have no previous logs to search
+                .filter(move |c| !previous || has_previous_instance(pod, &c.name))
+                .map(move |c| (config.parser_for(pod, &c.name), c))
         })
-        .unwrap_or_default();
+        .collect();
 
-    // Get all pods for this deployment
-    let pods: Api<Pod> = Api::namespaced(client.clone(), &namespace);
-    let pod_list = pods.list(&ListParams::default().labels(&selector)).await?;
+    let search_id = options.search_id.clone();
+    state
+        .tasks
+        .run_cancellable(
+            search_id.as_deref(),
+            search_containers(app, pods, targets, options, window, query),
+        )
+        .await
+}
 
-    let mut results: Vec<LogSearchResult> = Vec::new();
+/// Abort a running search started with a `search_id`
+#[tauri::command]
+pub async fn cancel_search(
+    state: State<'_, AppState>,
+    search_id: String,
+) -> Result<bool, K8sError> {
+    Ok(state.tasks.cancel(&search_id))
+}
 
-    for pod in pod_list.items {
-        let pod_name = pod.metadata.name.clone().unwrap_or_default();
+/// Check a search query without running it, so the UI can highlight errors as
+/// the user types
+#[tauri::command]
+pub async fn validate_log_query(query: String) -> Result<(), K8sError> {
+    Query::parse(&query)?;
+    Ok(())
+}
 
-        // Get containers
-        let containers: Vec<String> = pod
-            .spec
-            .as_ref()
-            .map(|s| s.containers.iter().map(|c| c.name.clone()).collect())
-            .unwrap_or_default();
-
-        for container_name in containers {
-            let mut params = LogParams {
-                timestamps: true,
-                container: Some(container_name.clone()),
-                ..Default::default()
-            };
-
-            if let Some(since) = since_seconds {
-                params.since_seconds = Some(since);
+/// Search the given (pod, container) pairs with bounded concurrency, emitting a
+/// `log-search-progress` event as each container finishes
+async fn search_containers(
+    app: AppHandle,
+    pods: Api<Pod>,
+    targets: Vec<(LineParser, ContainerSource)>,
+    options: LogSearchOptions,
+    window: TimeWindow,
+    query: Option<Query>,
+) -> LogSearchResponse {
+    let total = targets.len();
+    let parallelism = options
+        .parallelism
+        .unwrap_or(DEFAULT_SEARCH_PARALLELISM)
+        .max(1);
+
+    let mut outcomes = futures::stream::iter(targets)
+        .map(|(parser, container)| {
+            let pods = pods.clone();
+            let options = &options;
+            let query = query.as_ref();
+            async move {
+                let result = search_container(&pods, &parser, options, window, query).await;
+                (parser.pod_name, container, result)
             }
+        })
+        .buffer_unordered(parallelism);
+
+    let mut response = LogSearchResponse::default();
+    let mut completed = 0;
+
+    while let Some((pod_name, container, result)) = outcomes.next().await {
+        completed += 1;
 
-            // Limit to reasonable number of lines for search
-            params.tail_lines = Some(1000);
-
-            match pods.logs(&pod_name, &params).await {
-                Ok(logs) => {
-                    let entries: Vec<LogEntry> = logs
-                        .lines()
-                        .map(|line| parse_log_line(line, &pod_name, &container_name))
-                        .filter(|entry| {
-                            let keyword_match = keyword
-                                .as_ref()
-                                .map(|kw| {
-                                    entry.message.to_lowercase().contains(&kw.to_lowercase())
-                                        || entry.raw.to_lowercase().contains(&kw.to_lowercase())
-                                })
-                                .unwrap_or(true);
-
-                            let level_match = log_level
-                                .as_ref()
-                                .map(|lv| {
-                                    entry
-                                        .level
-                                        .as_ref()
-                                        .map(|l| l.to_uppercase() == lv.to_uppercase())
-                                        .unwrap_or(false)
-                                })
-                                .unwrap_or(true);
-
-                            keyword_match && level_match
-                        })
-                        .collect();
-
-                    if !entries.is_empty() {
-                        results.push(LogSearchResult {
-                            pod_name: pod_name.clone(),
-                            container_name: container_name.clone(),
-                            total_matches: entries.len() as i32,
-                            entries,
-                        });
-                    }
+        let mut progress = LogSearchProgress {
+            search_id: options.search_id.clone(),
+            completed,
+            total,
+            result: None,
+            failure: None,
+        };
+
+        match result {
+            Ok(entries) => {
+                if !entries.is_empty() {
+                    let result = LogSearchResult {
+                        pod_name,
+                        container_name: container.name,
+                        container_kind: container.kind,
+                        total_matches: entries.len() as i32,
+                        entries,
+                    };
+                    progress.result = Some(result.clone());
+                    response.results.push(result);
                 }
-                Err(_) => continue, // Skip pods we can't get logs from
+            }
+            Err(e) => {
+                let failure = LogSearchFailure {
+                    pod_name,
+                    container_name: container.name,
+                    error: e.into(),
+                };
+                progress.failure = Some(failure.clone());
+                response.failures.push(failure);
             }
         }
+
+        let _ = app.emit(SEARCH_PROGRESS_EVENT, progress);
     }
 
-    Ok(results)
+    // Completion order is nondeterministic, present results in a stable order
+    response
+        .results
+        .sort_by(|a, b| (&a.pod_name, &a.container_name).cmp(&(&b.pod_name, &b.container_name)));
+
+    response
+}
+
+/// Fetch and filter the logs of a single container
+async fn search_container(
+    pods: &Api<Pod>,
+    parser: &LineParser,
+    options: &LogSearchOptions,
+    window: TimeWindow,
+    query: Option<&Query>,
+) -> Result<Vec<LogEntry>, K8sError> {
+    let params = LogParams {
+        timestamps: true,
+        container: Some(parser.container_name.clone()),
+        since_seconds: options.since_seconds,
+        previous: options.previous.unwrap_or(false),
+        // Limit to reasonable number of lines for search
+        tail_lines: Some(SEARCH_TAIL_LINES),
+        ..Default::default()
+    };
+
+    let logs = fetch_logs(pods, &parser.pod_name, params, window).await?;
+
+    // Group before filtering so stack traces stay with their (e.g. ERROR) entry
+    Ok(parse_log_text(&logs, parser)
+        .into_iter()
+        .filter(|entry| matches_filters(entry, options, query))
+        .collect())
 }
 
-/// Parse a single log line, detecting JSON vs plain text
-fn parse_log_line(line: &str, pod_name: &str, container_name: &str) -> LogEntry {
+/// Absolute time range of a fetch or search
+#[derive(Clone, Copy, Default)]
+pub(crate) struct TimeWindow {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl TimeWindow {
+    pub(crate) fn parse(
+        since_seconds: Option<i64>,
+        since_time: Option<&str>,
+        until_time: Option<&str>,
+    ) -> Result<Self, K8sError> {
+        let parse = |name: &str, value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.to_utc())
+                .map_err(|e| K8sError::InvalidTimeRange(format!("{} '{}': {}", name, value, e)))
+        };
+
+        if since_seconds.is_some() && since_time.is_some() {
+            return Err(K8sError::InvalidTimeRange(
+                "since_seconds and since_time can't be combined".to_string(),
+            ));
+        }
+
+        let window = Self {
+            since: since_time.map(|t| parse("since_time", t)).transpose()?,
+            until: until_time.map(|t| parse("until_time", t)).transpose()?,
+        };
+        if let (Some(since), Some(until)) = (window.since, window.until) {
+            if until < since {
+                return Err(K8sError::InvalidTimeRange(
+                    "until_time is before since_time".to_string(),
+                ));
+            }
+        }
+        Ok(window)
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+}
+
+/// Fetch a container's logs, limited to `window`.
+///
+/// The API only takes a lower bound, truncated to the second, so lines are
+/// also trimmed by their kubelet timestamp and reading stops at the first
+/// line past the upper bound. `tail_lines` then applies to what is left.
+pub(crate) async fn fetch_logs(
+    pods: &Api<Pod>,
+    pod_name: &str,
+    mut params: LogParams,
+    window: TimeWindow,
+) -> Result<String, K8sError> {
+    if !window.is_bounded() {
+        return Ok(pods.logs(pod_name, &params).await?);
+    }
+
+    params.since_time = window.since;
+    // The API counts tail lines from the end of the log, past the window
+    let tail_lines = match window.until {
+        Some(_) => params.tail_lines.take(),
+        None => None,
+    };
+
+    let mut lines = pods.log_stream(pod_name, &params).await?.lines();
+    let mut kept: VecDeque<String> = VecDeque::new();
+
+    while let Some(line) = lines.next().await {
+        let line = line?;
+        let time = extract_timestamp(&line)
+            .0
+            .and_then(|ts| timestamp::parse(&ts, None));
+
+        if let Some(time) = time {
+            if window.until.is_some_and(|until| time > until) {
+                break;
+            }
+            if window.since.is_some_and(|since| time < since) {
+                continue;
+            }
+        }
+
+        kept.push_back(line);
+        if tail_lines.is_some_and(|tail| kept.len() as i64 > tail) {
+            kept.pop_front();
+        }
+    }
+
+    Ok(Vec::from(kept).join("\n"))
+}
+
+/// Apply the keyword, level and query filters of a search
+fn matches_filters(entry: &LogEntry, options: &LogSearchOptions, query: Option<&Query>) -> bool {
+    let keyword_match = options
+        .keyword
+        .as_ref()
+        .map(|kw| {
+            entry.message.to_lowercase().contains(&kw.to_lowercase())
+                || entry.raw.to_lowercase().contains(&kw.to_lowercase())
+        })
+        .unwrap_or(true);
+
+    let level_match = options
+        .log_level
+        .as_ref()
+        .map(|lv| {
+            entry
+                .level
+                .as_ref()
+                .map(|l| l.to_uppercase() == lv.to_uppercase())
+                .unwrap_or(false)
+        })
+        .unwrap_or(true);
+
+    let query_match = query.map(|q| q.matches(entry)).unwrap_or(true);
+
+    keyword_match && level_match && query_match
+}
+
+/// Parse a single log line, detecting JSON, the built-in formats, logfmt or
+/// plain text
+pub(crate) fn parse_log_line(line: &str, parser: &LineParser) -> LogEntry {
+    // Parse colored output without its escape codes and keep the styling of
+    // the message aside. Formats cut the message out of the line, so it's
+    // found in the stripped text; a message rebuilt from parts (e.g. a JSON
+    // string with escapes) goes uncolored.
+    if ansi::has_escapes(line) {
+        let (_, body) = extract_timestamp(line);
+        let spans = ansi::parse(body);
+        let mut entry = parse_log_line(&ansi::strip(line), parser);
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        entry.spans = text
+            .find(&entry.message)
+            .map(|start| ansi::slice(&spans, start, start + entry.message.len()));
+        return entry;
+    }
+
     // Extract timestamp if present (kubectl adds it with --timestamps)
     let (timestamp, rest) = extract_timestamp(line);
 
-    // Try to parse as JSON
-    if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(rest) {
-        parse_json_log(json_value, timestamp, pod_name, container_name, line)
+    let kubelet_time = timestamp
+        .as_deref()
+        .and_then(|ts| timestamp::parse(ts, None));
+
+    let mut entry = if let Some(entry) = parser.apply_rule(rest, timestamp.clone(), line) {
+        // A user-defined format assigned to the container takes precedence
+        entry
+    } else if parser.plain_only() {
+        parse_plain_log(rest, timestamp, line, parser)
+    } else if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(rest) {
+        parse_json_log(json_value, timestamp, line, parser)
+    } else if let Some(parsed) = parser.parse_format(rest) {
+        parse_format_log(parsed, timestamp, line, parser)
+    } else if let Some(pairs) = logfmt::parse(rest) {
+        parse_logfmt_log(pairs, timestamp, line, parser)
     } else {
-        parse_plain_log(rest, timestamp, pod_name, container_name, line)
-    }
+        parse_plain_log(rest, timestamp, line, parser)
+    };
+
+    // Fall back to the kubelet's time when the line's own can't be read
+    entry.timestamp_utc = entry
+        .timestamp
+        .as_deref()
+        .and_then(|ts| timestamp::parse(ts, kubelet_time))
+        .or(kubelet_time)
+        .map(timestamp::format);
+    entry
+}
+
+/// Parse fetched log text into entries, folding continuation lines
+pub(crate) fn parse_log_text(logs: &str, parser: &LineParser) -> Vec<LogEntry> {
+    let mut grouper = MultilineGrouper::new(&parser.multiline);
+    let mut entries: Vec<LogEntry> = logs
+        .lines()
+        .filter_map(|line| grouper.push(parse_log_line(line, parser)))
+        .collect();
+    entries.extend(grouper.flush());
+    entries
 }
 
 /// Extract timestamp from the beginning of a log line
-fn extract_timestamp(line: &str) -> (Option<String>, &str) {
+pub(crate) fn extract_timestamp(line: &str) -> (Option<String>, &str) {
     // Kubernetes timestamps are in RFC3339 format at the start of the line
     // e.g., "2024-12-26T10:23:45.123456789Z log message"
     if line.len() > 30 && line.chars().nth(4) == Some('-') && line.chars().nth(7) == Some('-') {
@@ -193,9 +697,8 @@ fn extract_timestamp(line: &str) -> (Option<String>, &str) {
 fn parse_json_log(
     json: serde_json::Value,
     fallback_timestamp: Option<String>,
-    pod_name: &str,
-    container_name: &str,
     raw: &str,
+    parser: &LineParser,
 ) -> LogEntry {
     // Extract level from common fields
     let level = json
@@ -204,7 +707,7 @@ fn parse_json_log(
         .or_else(|| json.get("log_level"))
         .or_else(|| json.get("lvl"))
         .and_then(|v| v.as_str())
-        .map(|s| normalize_log_level(s));
+        .map(|level| parser.normalize_level(level));
 
     // Extract message from common fields
     let message = json
@@ -221,18 +724,191 @@ fn parse_json_log(
         .or_else(|| json.get("time"))
         .or_else(|| json.get("ts"))
         .or_else(|| json.get("@timestamp"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
+        .and_then(|v| match v {
+            serde_json::Value::String(s) => Some(s.clone()),
+            // Epoch seconds or milliseconds
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
         .or(fallback_timestamp);
 
+    let mut fields = BTreeMap::new();
+    if json.is_object() || json.is_array() {
+        flatten_json(&json, String::new(), &mut fields);
+    }
+
     LogEntry {
         timestamp,
+        timestamp_utc: None,
         level,
         message,
         raw: raw.to_string(),
         is_json: true,
-        pod_name: pod_name.to_string(),
-        container_name: container_name.to_string(),
+        pod_name: parser.pod_name.clone(),
+        container_name: parser.container_name.clone(),
+        source: LogSource::Container,
+        well_known: well_known_fields(&fields),
+        fields,
+        spans: None,
+    }
+}
+
+/// Flatten nested objects and arrays into dot paths (`http.request.method`,
+/// `tags.0`), rendering leaves as strings. Nulls and empty containers are skipped.
+fn flatten_json(value: &serde_json::Value, path: String, fields: &mut BTreeMap<String, String>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                flatten_json(value, child(key), fields);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (index, value) in items.iter().enumerate() {
+                flatten_json(value, child(&index.to_string()), fields);
+            }
+        }
+        serde_json::Value::Null => {}
+        serde_json::Value::String(s) => {
+            fields.insert(path, s.clone());
+        }
+        other => {
+            fields.insert(path, other.to_string());
+        }
+    }
+}
+
+/// Pick the first of `keys` present in `fields`
+fn first_field(fields: &BTreeMap<String, String>, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| fields.get(*key)).cloned()
+}
+
+/// Extract fields that common logging libraries (zap, logrus, slog, logback,
+/// OpenTelemetry, ECS...) emit under varying names
+pub(crate) fn well_known_fields(fields: &BTreeMap<String, String>) -> WellKnownFields {
+    // slog and ECS report the call site as an object
+    let caller =
+        first_field(fields, &["caller", "source", "src", "log.origin.file.name"]).or_else(|| {
+            let file = first_field(fields, &["source.file", "caller.file"])?;
+            Some(match first_field(fields, &["source.line", "caller.line"]) {
+                Some(line) => format!("{}:{}", file, line),
+                None => file,
+            })
+        });
+
+    WellKnownFields {
+        logger: first_field(
+            fields,
+            &["logger", "logger_name", "loggerName", "log.logger"],
+        ),
+        caller,
+        trace_id: first_field(
+            fields,
+            &["trace_id", "traceId", "traceID", "trace.id", "dd.trace_id"],
+        ),
+        span_id: first_field(
+            fields,
+            &["span_id", "spanId", "spanID", "span.id", "dd.span_id"],
+        ),
+        error: first_field(
+            fields,
+            &[
+                "error",
+                "err",
+                "error.message",
+                "exception",
+                "exception.message",
+            ],
+        ),
+        stack: first_field(
+            fields,
+            &[
+                "stack",
+                "stacktrace",
+                "stack_trace",
+                "error.stack",
+                "error.stack_trace",
+                "exception.stacktrace",
+            ],
+        ),
+    }
+}
+
+/// Parse logfmt structured log. Level, message and time are extracted, every
+/// other key ends up in `fields`.
+fn parse_logfmt_log(
+    pairs: Vec<(String, String)>,
+    fallback_timestamp: Option<String>,
+    raw: &str,
+    parser: &LineParser,
+) -> LogEntry {
+    let mut level = None;
+    let mut message = None;
+    let mut timestamp = None;
+    let mut fields = BTreeMap::new();
+
+    for (key, value) in pairs {
+        match key.as_str() {
+            "level" | "lvl" | "severity" | "log_level" if level.is_none() => {
+                level = Some(parser.normalize_level(&value));
+            }
+            "msg" | "message" if message.is_none() => message = Some(value),
+            "time" | "ts" | "timestamp" | "@timestamp" if timestamp.is_none() => {
+                timestamp = Some(value);
+            }
+            _ => {
+                fields.insert(key, value);
+            }
+        }
+    }
+
+    LogEntry {
+        timestamp: timestamp.or(fallback_timestamp),
+        timestamp_utc: None,
+        level,
+        message: message.unwrap_or_else(|| extract_timestamp(raw).1.to_string()),
+        raw: raw.to_string(),
+        is_json: false,
+        pod_name: parser.pod_name.clone(),
+        container_name: parser.container_name.clone(),
+        source: LogSource::Container,
+        well_known: well_known_fields(&fields),
+        fields,
+        spans: None,
+    }
+}
+
+/// Build the entry of a line split by a built-in format. The line keeps its own
+/// timestamp; the kubectl one only fills in what it leaves out (e.g. klog's
+/// year) when normalizing to `timestamp_utc`.
+fn parse_format_log(
+    parsed: formats::Parsed,
+    timestamp: Option<String>,
+    raw: &str,
+    parser: &LineParser,
+) -> LogEntry {
+    let fields: BTreeMap<String, String> = parsed.fields.into_iter().collect();
+
+    LogEntry {
+        timestamp: parsed.timestamp.or(timestamp),
+        timestamp_utc: None,
+        level: parsed.level.map(|level| parser.normalize_level(&level)),
+        message: parsed.message,
+        raw: raw.to_string(),
+        is_json: false,
+        pod_name: parser.pod_name.clone(),
+        container_name: parser.container_name.clone(),
+        source: LogSource::Container,
+        well_known: well_known_fields(&fields),
+        fields,
+        spans: None,
     }
 }
 
@@ -240,26 +916,30 @@ fn parse_json_log(
 fn parse_plain_log(
     content: &str,
     timestamp: Option<String>,
-    pod_name: &str,
-    container_name: &str,
     raw: &str,
+    parser: &LineParser,
 ) -> LogEntry {
     // Try to detect log level from content
     let level = detect_log_level(content);
 
     LogEntry {
         timestamp,
+        timestamp_utc: None,
         level,
         message: content.to_string(),
         raw: raw.to_string(),
         is_json: false,
-        pod_name: pod_name.to_string(),
-        container_name: container_name.to_string(),
+        pod_name: parser.pod_name.clone(),
+        container_name: parser.container_name.clone(),
+        source: LogSource::Container,
+        fields: BTreeMap::new(),
+        well_known: WellKnownFields::default(),
+        spans: None,
     }
 }
 
 /// Normalize log level to standard values
-fn normalize_log_level(level: &str) -> String {
+pub(crate) fn normalize_log_level(level: &str) -> String {
     let upper = level.to_uppercase();
     match upper.as_str() {
         "ERROR" | "ERR" | "FATAL" | "CRITICAL" | "CRIT" => "ERROR".to_string(),
@@ -305,3 +985,553 @@ fn detect_log_level(content: &str) -> Option<String> {
 
     None
 }
+
+/// Maximum number of lines folded into one entry when the caller doesn't say
+const DEFAULT_MULTILINE_MAX_LINES: usize = 1000;
+
+/// Compiled multiline settings, shared by the groupers of a fetch, search or stream
+#[derive(Clone)]
+pub(crate) struct MultilineRules {
+    enabled: bool,
+    patterns: Vec<Regex>,
+    max_lines: usize,
+}
+
+impl MultilineRules {
+    /// Compile the caller's options; grouping is on by default
+    pub(crate) fn compile(options: Option<&MultilineOptions>) -> Result<Self, K8sError> {
+        let patterns = options
+            .map(|o| o.continuation_patterns.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            enabled: options.and_then(|o| o.enabled).unwrap_or(true),
+            patterns,
+            max_lines: options
+                .and_then(|o| o.max_lines)
+                .unwrap_or(DEFAULT_MULTILINE_MAX_LINES)
+                .max(1),
+        })
+    }
+}
+
+fn plain_span(text: &str) -> StyledSpan {
+    StyledSpan {
+        text: text.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Fully qualified Java exception header, e.g. `java.lang.IllegalStateException: boom`,
+/// which loggers print on the line after the message
+fn java_exception() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^([a-zA-Z_$][\w$]*\.)+[A-Z][\w$]*(Exception|Error|Throwable)(:|$)")
+            .expect("valid regex")
+    })
+}
+
+/// Unindented lines of a Go goroutine dump: `goroutine 1 [running]:`,
+/// `created by ...`, `[signal SIGSEGV: ...]` and function frames such as
+/// `main.main()` or `net/http.(*conn).serve(0xc000...)`, whose file line
+/// follows tab-indented. A frame is a package-qualified call with no spaces
+/// before its `(`, so ordinary lines like `connected (10ms)` don't match.
+fn go_trace_line() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            r"^(?:goroutine \d+ \[.*\]:",
+            r"|created by \S.*",
+            r"|\[signal .*\]",
+            r"|panic\(.*\)",
+            r"|(?:[\w.\-]+/)*[\w\-]+(?:\.\(\*?[^\s()]+\))?\.[^\s()]+\(.*\))$",
+        ))
+        .expect("valid regex")
+    })
+}
+
+/// Kind of stack trace being folded, whose lines follow their own rules
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Trace {
+    None,
+    /// Python `Traceback (most recent call last):` block, closed by the
+    /// unindented exception line
+    Python,
+    /// Go panic with its goroutine dumps
+    Go,
+}
+
+/// Folds continuation lines (indented stack frames, `Caused by:`, Python
+/// tracebacks, Go goroutine dumps...) into the entry they belong to.
+///
+/// Entries go in one at a time through `push`, which hands back each entry
+/// once the next line shows it is complete. `flush` returns the last one.
+pub(crate) struct MultilineGrouper<'a> {
+    rules: &'a MultilineRules,
+    pending: Option<LogEntry>,
+    lines: usize,
+    trace: Trace,
+}
+
+impl<'a> MultilineGrouper<'a> {
+    pub(crate) fn new(rules: &'a MultilineRules) -> Self {
+        Self {
+            rules,
+            pending: None,
+            lines: 0,
+            trace: Trace::None,
+        }
+    }
+
+    /// Whether an entry is held back waiting for possible continuation lines
+    pub(crate) fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Add the next entry, returning the previous one if it is now complete
+    pub(crate) fn push(&mut self, entry: LogEntry) -> Option<LogEntry> {
+        if !self.rules.enabled {
+            return Some(entry);
+        }
+
+        if self.pending.is_some()
+            && self.lines < self.rules.max_lines
+            && self.is_continuation(&entry)
+        {
+            self.append(entry);
+            return None;
+        }
+
+        let complete = self.pending.take();
+        self.start(entry);
+        complete
+    }
+
+    /// Return the entry being held back, if any
+    pub(crate) fn flush(&mut self) -> Option<LogEntry> {
+        self.trace = Trace::None;
+        self.pending.take()
+    }
+
+    fn start(&mut self, mut entry: LogEntry) {
+        let (_, body) = extract_timestamp(&entry.raw);
+        self.trace = if body.starts_with("panic: ") || body.starts_with("fatal error: ") {
+            if entry.level.is_none() {
+                entry.level = Some("ERROR".to_string());
+            }
+            Trace::Go
+        } else {
+            Trace::None
+        };
+        self.lines = 1;
+        self.pending = Some(entry);
+    }
+
+    fn append(&mut self, entry: LogEntry) {
+        let Some(pending) = self.pending.as_mut() else {
+            return;
+        };
+        let (_, body) = extract_timestamp(&entry.raw);
+
+        // Keep the coloring of the whole group if any line had some
+        if pending.spans.is_some() || entry.spans.is_some() {
+            let mut spans = pending
+                .spans
+                .take()
+                .unwrap_or_else(|| vec![plain_span(&pending.message)]);
+            spans.push(plain_span("\n"));
+            // The whole line is appended, so its styling only fits if it's
+            // all message
+            spans.extend(match entry.spans {
+                Some(line_spans) if entry.message == body => line_spans,
+                _ => vec![plain_span(body)],
+            });
+            pending.spans = Some(spans);
+        }
+
+        pending.message.push('\n');
+        pending.message.push_str(body);
+        pending.raw.push('\n');
+        pending.raw.push_str(&entry.raw);
+
+        // An uncaught exception or panic makes an unleveled entry an error
+        if self.trace != Trace::None && pending.level.is_none() {
+            pending.level = Some("ERROR".to_string());
+        }
+        self.lines += 1;
+    }
+
+    fn is_continuation(&mut self, entry: &LogEntry) -> bool {
+        if entry.is_json {
+            return false;
+        }
+
+        let (_, body) = extract_timestamp(&entry.raw);
+        let indented = body.starts_with([' ', '\t']) && !body.trim().is_empty();
+
+        match self.trace {
+            Trace::Python if indented => return true,
+            Trace::Python => {
+                self.trace = Trace::None;
+                return !body.trim().is_empty();
+            }
+            Trace::Go if body.trim().is_empty() || indented || go_trace_line().is_match(body) => {
+                return true;
+            }
+            Trace::Go => self.trace = Trace::None,
+            Trace::None => {}
+        }
+
+        if body.starts_with("Traceback (most recent call last):") {
+            self.trace = Trace::Python;
+            return true;
+        }
+
+        if body.starts_with("goroutine ") && body.ends_with("]:") {
+            self.trace = Trace::Go;
+            return true;
+        }
+
+        indented
+            || java_exception().is_match(body)
+            || body.starts_with("Caused by: ")
+            || body.starts_with("... ")
+            || self.rules.patterns.iter().any(|p| p.is_match(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattens_nested_json_into_dot_paths() {
+        let line = r#"{"level":"warn","msg":"slow request","http":{"method":"GET","status":503},"tags":["a","b"],"user":null,"empty":{}}"#;
+        let entry = parse_log_line(line, &LineParser::plain());
+
+        assert!(entry.is_json);
+        assert_eq!(entry.level.as_deref(), Some("WARN"));
+        assert_eq!(entry.message, "slow request");
+        assert_eq!(entry.fields["http.method"], "GET");
+        assert_eq!(entry.fields["http.status"], "503");
+        assert_eq!(entry.fields["tags.0"], "a");
+        assert_eq!(entry.fields["tags.1"], "b");
+        assert!(!entry.fields.contains_key("user"));
+        assert!(!entry.fields.keys().any(|k| k.starts_with("empty")));
+    }
+
+    #[test]
+    fn picks_well_known_fields_under_their_common_names() {
+        let zap = parse_log_line(
+            r#"{"level":"error","logger":"http","caller":"server.go:42","traceId":"abc","span_id":"def","error":"boom","stacktrace":"main.go:1"}"#,
+            &LineParser::plain(),
+        );
+        assert_eq!(zap.well_known.logger.as_deref(), Some("http"));
+        assert_eq!(zap.well_known.caller.as_deref(), Some("server.go:42"));
+        assert_eq!(zap.well_known.trace_id.as_deref(), Some("abc"));
+        assert_eq!(zap.well_known.span_id.as_deref(), Some("def"));
+        assert_eq!(zap.well_known.error.as_deref(), Some("boom"));
+        assert_eq!(zap.well_known.stack.as_deref(), Some("main.go:1"));
+
+        // slog and ECS nest these under objects
+        let slog = parse_log_line(
+            r#"{"msg":"hi","source":{"file":"main.go","line":7},"trace":{"id":"t1"},"error":{"message":"bad"}}"#,
+            &LineParser::plain(),
+        );
+        assert_eq!(slog.well_known.caller.as_deref(), Some("main.go:7"));
+        assert_eq!(slog.well_known.trace_id.as_deref(), Some("t1"));
+        assert_eq!(slog.well_known.error.as_deref(), Some("bad"));
+        assert_eq!(slog.well_known.logger, None);
+    }
+
+    #[test]
+    fn formats_keep_their_own_timestamp() {
+        let line = r#"2024-10-26T12:00:05.000000000Z [2024-10-26T12:00:00.123Z] "GET /health HTTP/1.1" 200 - 0 2 1 - "10.0.0.1" "kube-probe" "id-1" "api" "-""#;
+        let entry = parse_log_line(line, &LineParser::plain());
+        assert_eq!(
+            entry.timestamp.as_deref(),
+            Some("2024-10-26T12:00:00.123+00:00")
+        );
+        assert_eq!(
+            entry.timestamp_utc.as_deref(),
+            Some("2024-10-26T12:00:00.123000000Z")
+        );
+
+        // klog's has neither year nor offset, which only the normalized time gets
+        let line =
+            "2024-10-26T12:00:05.000000000Z I1026 14:00:00.123456       1 main.go:1] started";
+        let entry = parse_log_line(line, &LineParser::plain());
+        assert_eq!(entry.timestamp.as_deref(), Some("1026 14:00:00.123456"));
+        assert_eq!(
+            entry.timestamp_utc.as_deref(),
+            Some("2024-10-26T12:00:00.123456000Z")
+        );
+
+        // Lines without one get the kubectl timestamp
+        let entry = parse_log_line(
+            "2024-10-26T12:00:05.000000000Z listening",
+            &LineParser::plain(),
+        );
+        assert_eq!(
+            entry.timestamp.as_deref(),
+            Some("2024-10-26T12:00:05.000000000Z")
+        );
+    }
+
+    #[test]
+    fn colors_follow_the_message() {
+        let line =
+            "2024-10-26T12:00:00.000000000Z level=info msg=\"\x1b[1mready\x1b[0m on :80\" port=80";
+        let entry = parse_log_line(line, &LineParser::plain());
+
+        assert_eq!(entry.message, "ready on :80");
+        let spans = entry.spans.unwrap();
+        let text: String = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(text, entry.message);
+        assert!(spans[0].bold && spans[0].text == "ready");
+    }
+
+    #[test]
+    fn logfmt_lines_get_well_known_fields_too() {
+        let entry = parse_log_line(
+            "level=info msg=ok trace_id=xyz caller=db.go:9",
+            &LineParser::plain(),
+        );
+        assert_eq!(entry.well_known.trace_id.as_deref(), Some("xyz"));
+        assert_eq!(entry.well_known.caller.as_deref(), Some("db.go:9"));
+    }
+
+    fn rules(options: MultilineOptions) -> MultilineRules {
+        MultilineRules::compile(Some(&options)).unwrap()
+    }
+
+    fn group(rules: &MultilineRules, lines: &[&str]) -> Vec<LogEntry> {
+        let mut grouper = MultilineGrouper::new(rules);
+        let mut entries: Vec<LogEntry> = lines
+            .iter()
+            .filter_map(|line| grouper.push(LogEntry::from_message(line)))
+            .collect();
+        entries.extend(grouper.flush());
+        entries
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    #[test]
+    fn folds_java_stack_traces() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let entries = group(
+            &rules,
+            &[
+                "ERROR request failed",
+                "java.lang.IllegalStateException: boom",
+                "\tat com.shop.Api.handle(Api.java:42)",
+                "Caused by: java.io.IOException: reset",
+                "\t... 3 more",
+                "INFO next request",
+            ],
+        );
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0]
+            .message
+            .starts_with("ERROR request failed\njava.lang."));
+        assert!(entries[0].message.ends_with("\t... 3 more"));
+        assert_eq!(entries[1].message, "INFO next request");
+    }
+
+    #[test]
+    fn folds_python_tracebacks_up_to_the_exception_line() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let entries = group(
+            &rules,
+            &[
+                "Exception in worker",
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 3, in <module>",
+                "    main()",
+                "ValueError: bad input",
+                "worker restarted",
+            ],
+        );
+
+        assert_eq!(
+            messages(&entries),
+            [
+                "Exception in worker\nTraceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    main()\nValueError: bad input",
+                "worker restarted",
+            ]
+        );
+        assert_eq!(entries[0].level.as_deref(), Some("ERROR"));
+        assert_eq!(entries[1].level, None);
+    }
+
+    #[test]
+    fn folds_go_panics_with_their_goroutine_dumps() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let entries = group(
+            &rules,
+            &[
+                "panic: runtime error: index out of range",
+                "",
+                "goroutine 1 [running]:",
+                "main.main()",
+                "\t/app/main.go:10 +0x1d",
+                "exit status 2",
+            ],
+        );
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].level.as_deref(), Some("ERROR"));
+        assert!(entries[0].message.ends_with("\t/app/main.go:10 +0x1d"));
+        assert_eq!(entries[1].message, "exit status 2");
+    }
+
+    #[test]
+    fn go_traces_end_at_the_first_non_frame_line() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let entries = group(
+            &rules,
+            &[
+                "panic: boom",
+                "goroutine 1 [running]:",
+                "net/http.(*conn).serve(0xc000123000)",
+                "\t/usr/local/go/src/net/http/server.go:2009 +0x8f",
+                "reconnected to db (10ms)",
+            ],
+        );
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].message, "reconnected to db (10ms)");
+    }
+
+    #[test]
+    fn applies_custom_continuation_patterns() {
+        let rules = rules(MultilineOptions {
+            continuation_patterns: vec![r"^\| ".to_string()],
+            ..Default::default()
+        });
+        let entries = group(&rules, &["table:", "| a | b |", "| 1 | 2 |", "done"]);
+
+        assert_eq!(messages(&entries), ["table:\n| a | b |\n| 1 | 2 |", "done"]);
+    }
+
+    #[test]
+    fn caps_entries_at_max_lines() {
+        let rules = rules(MultilineOptions {
+            max_lines: Some(2),
+            ..Default::default()
+        });
+        let entries = group(
+            &rules,
+            &["ERROR boom", "  frame 1", "  frame 2", "  frame 3"],
+        );
+
+        assert_eq!(
+            messages(&entries),
+            ["ERROR boom\n  frame 1", "  frame 2\n  frame 3"]
+        );
+    }
+
+    #[test]
+    fn flush_returns_the_held_back_entry_once() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let mut grouper = MultilineGrouper::new(&rules);
+
+        assert!(grouper.push(LogEntry::from_message("first")).is_none());
+        assert!(grouper.push(LogEntry::from_message("  more")).is_none());
+        assert!(grouper.has_pending());
+
+        let entry = grouper.flush().unwrap();
+        assert_eq!(entry.message, "first\n  more");
+        assert_eq!(entry.raw, "first\n  more");
+        assert!(grouper.flush().is_none());
+        assert!(!grouper.has_pending());
+    }
+
+    #[test]
+    fn disabled_grouping_passes_every_line_through() {
+        let rules = rules(MultilineOptions {
+            enabled: Some(false),
+            ..Default::default()
+        });
+        let entries = group(&rules, &["ERROR boom", "  frame 1", "Caused by: x"]);
+
+        assert_eq!(
+            messages(&entries),
+            ["ERROR boom", "  frame 1", "Caused by: x"]
+        );
+    }
+
+    #[test]
+    fn json_lines_are_never_continuations() {
+        let rules = MultilineRules::compile(None).unwrap();
+        let mut grouper = MultilineGrouper::new(&rules);
+        grouper.push(LogEntry::from_message("starting"));
+
+        let json = LogEntry {
+            is_json: true,
+            ..LogEntry::from_message("  indented json message")
+        };
+        assert_eq!(grouper.push(json).unwrap().message, "starting");
+    }
+
+    fn page(
+        lines: &[&str],
+        ended: bool,
+        cursor: Option<&LogCursor>,
+    ) -> (Vec<LogEntry>, Option<LogCursor>) {
+        let fetched = FetchedLogs {
+            logs: lines.join("\n"),
+            parser: LineParser::plain(),
+            ended,
+        };
+        parse_page(&fetched, cursor.cloned())
+    }
+
+    #[test]
+    fn polls_hold_back_the_last_entry_until_it_ends() {
+        let first = [
+            "2024-10-26T12:00:00.100000000Z INFO starting",
+            "2024-10-26T12:00:00.200000000Z ERROR request failed",
+            "2024-10-26T12:00:00.200000000Z java.lang.IllegalStateException: boom",
+        ];
+        let (entries, cursor) = page(&first, false, None);
+        assert_eq!(messages(&entries), ["INFO starting"]);
+
+        // The next poll gets the whole trace again, with what came after it
+        let second = [
+            first[1],
+            first[2],
+            "2024-10-26T12:00:00.300000000Z \tat com.shop.Api.handle(Api.java:42)",
+            "2024-10-26T12:00:00.400000000Z INFO next request",
+        ];
+        let (entries, cursor) = page(&second, false, cursor.as_ref());
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].message.starts_with("ERROR request failed\n"));
+        assert!(entries[0].message.ends_with("(Api.java:42)"));
+
+        let (entries, _) = page(&second[3..], false, cursor.as_ref());
+        assert!(entries.is_empty());
+        let (entries, _) = page(&second[3..], true, cursor.as_ref());
+        assert_eq!(messages(&entries), ["INFO next request"]);
+    }
+
+    #[test]
+    fn polls_without_a_complete_entry_keep_their_cursor() {
+        let lines = ["2024-10-26T12:00:00.100000000Z INFO starting"];
+        let (entries, cursor) = page(&lines, false, None);
+        assert!(entries.is_empty());
+        assert!(cursor.is_none());
+
+        let (entries, cursor) = page(&lines, true, None);
+        assert_eq!(messages(&entries), ["INFO starting"]);
+        assert!(cursor.is_some());
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, HashMap};
+
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use kube::api::{ApiResource, DynamicObject, ListParams};
+use kube::{Api, Client};
+use serde::Deserialize;
+use tauri::State;
+
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::workloads::list_workload_pods;
+use crate::models::{ContainerMetrics, PodMetrics, WorkloadKind};
+use crate::state::AppState;
+
+const METRICS_GROUP: &str = "metrics.k8s.io";
+
+/// Current CPU and memory usage of a pod's containers
+#[tauri::command]
+pub async fn get_pod_metrics(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    pod_name: String,
+) -> Result<PodMetrics, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("pods", &pod_name);
+
+    let pod: Pod = Api::namespaced(client.clone(), &namespace)
+        .get(&pod_name)
+        .await
+        .in_scope(&scope)?;
+
+    let sample = match metrics_api(&client, &namespace).get(&pod_name).await {
+        Ok(object) => Some(parse_sample(object)?),
+        // No sample yet for this pod, or no metrics API at all
+        Err(kube::Error::Api(response)) if response.code == 404 => {
+            ensure_metrics_api(&client).await.in_scope(&scope)?;
+            None
+        }
+        Err(e) => return Err(metrics_error(e)).in_scope(&scope),
+    };
+
+    Ok(pod_metrics(&pod, sample))
+}
+
+#[tauri::command]
+pub async fn get_deployment_metrics(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    deployment: String,
+) -> Result<Vec<PodMetrics>, K8sError> {
+    get_workload_metrics(
+        state,
+        context,
+        namespace,
+        WorkloadKind::Deployment,
+        deployment,
+    )
+    .await
+}
+
+/// Current CPU and memory usage of every pod of a workload
+#[tauri::command]
+pub async fn get_workload_metrics(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    kind: WorkloadKind,
+    name: String,
+) -> Result<Vec<PodMetrics>, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource(kind.resource(), &name);
+
+    let pods = list_workload_pods(&client, &namespace, kind, &name)
+        .await
+        .in_scope(&scope)?;
+
+    let mut samples: HashMap<String, Sample> = HashMap::new();
+    match metrics_api(&client, &namespace)
+        .list(&ListParams::default())
+        .await
+    {
+        Ok(list) => {
+            for object in list.items {
+                let pod_name = object.metadata.name.clone().unwrap_or_default();
+                samples.insert(pod_name, parse_sample(object)?);
+            }
+        }
+        Err(kube::Error::Api(response)) if response.code == 404 => {
+            ensure_metrics_api(&client).await.in_scope(&scope)?;
+        }
+        Err(e) => return Err(metrics_error(e)).in_scope(&scope),
+    }
+
+    let mut metrics: Vec<PodMetrics> = pods
+        .iter()
+        .map(|pod| {
+            let name = pod.metadata.name.as_deref().unwrap_or_default();
+            pod_metrics(pod, samples.remove(name))
+        })
+        .collect();
+    metrics.sort_by(|a, b| a.pod_name.cmp(&b.pod_name));
+
+    Ok(metrics)
+}
+
+/// `PodMetrics` of `metrics.k8s.io/v1beta1`, which k8s-openapi has no type for
+fn metrics_api(client: &Client, namespace: &str) -> Api<DynamicObject> {
+    let resource = ApiResource {
+        group: METRICS_GROUP.to_string(),
+        version: "v1beta1".to_string(),
+        api_version: format!("{}/v1beta1", METRICS_GROUP),
+        kind: "PodMetrics".to_string(),
+        plural: "pods".to_string(),
+    };
+    Api::namespaced_with(client.clone(), namespace, &resource)
+}
+
+/// Fail with `MetricsUnavailable` unless the cluster serves the metrics API
+async fn ensure_metrics_api(client: &Client) -> Result<(), K8sError> {
+    let groups = client.list_api_groups().await?;
+    if groups.groups.iter().any(|g| g.name == METRICS_GROUP) {
+        Ok(())
+    } else {
+        Err(K8sError::MetricsUnavailable(
+            "metrics.k8s.io is not served, is metrics-server installed?".to_string(),
+        ))
+    }
+}
+
+/// The metrics API is an aggregated API: a 503 means metrics-server is down
+fn metrics_error(error: kube::Error) -> K8sError {
+    match error {
+        kube::Error::Api(response) if response.code == 503 => K8sError::MetricsUnavailable(
+            format!("metrics-server is not responding: {}", response.message),
+        ),
+        error => error.into(),
+    }
+}
+
+/// The parts of a `PodMetrics` object used here
+#[derive(Deserialize)]
+struct Sample {
+    timestamp: Option<String>,
+    window: Option<String>,
+    #[serde(default)]
+    containers: Vec<ContainerSample>,
+}
+
+#[derive(Deserialize)]
+struct ContainerSample {
+    name: String,
+    #[serde(default)]
+    usage: BTreeMap<String, Quantity>,
+}
+
+fn parse_sample(object: DynamicObject) -> Result<Sample, K8sError> {
+    serde_json::from_value(object.data).map_err(|e| K8sError::Kube(kube::Error::SerdeError(e)))
+}
+
+/// Combine a pod's resource settings with its usage sample
+fn pod_metrics(pod: &Pod, sample: Option<Sample>) -> PodMetrics {
+    let (timestamp, window, mut usage) = match sample {
+        Some(sample) => (
+            sample.timestamp,
+            sample.window,
+            sample
+                .containers
+                .into_iter()
+                .map(|c| (c.name, c.usage))
+                .collect(),
+        ),
+        None => (None, None, HashMap::new()),
+    };
+
+    let containers = pod
+        .spec
+        .iter()
+        .flat_map(|spec| spec.containers.iter())
+        .map(|container| {
+            let usage = usage.remove(&container.name).unwrap_or_default();
+            let resources = container.resources.as_ref();
+            let requests = resources.and_then(|r| r.requests.as_ref());
+            let limits = resources.and_then(|r| r.limits.as_ref());
+
+            let millicores =
+                |q: Option<&Quantity>| q.and_then(parse_quantity).map(|cores| cores * 1000.0);
+            let bytes = |q: Option<&Quantity>| q.and_then(parse_quantity);
+
+            let cpu = millicores(usage.get("cpu"));
+            let memory = bytes(usage.get("memory"));
+            let cpu_limit = millicores(limits.and_then(|l| l.get("cpu")));
+            let memory_limit = bytes(limits.and_then(|l| l.get("memory")));
+
+            ContainerMetrics {
+                name: container.name.clone(),
+                cpu_millicores: cpu,
+                memory_bytes: memory,
+                cpu_request_millicores: millicores(requests.and_then(|r| r.get("cpu"))),
+                cpu_limit_millicores: cpu_limit,
+                memory_request_bytes: bytes(requests.and_then(|r| r.get("memory"))),
+                memory_limit_bytes: memory_limit,
+                cpu_percent_of_limit: percent(cpu, cpu_limit),
+                memory_percent_of_limit: percent(memory, memory_limit),
+            }
+        })
+        .collect();
+
+    PodMetrics {
+        pod_name: pod.metadata.name.clone().unwrap_or_default(),
+        timestamp,
+        window,
+        containers,
+    }
+}
+
+fn percent(usage: Option<f64>, limit: Option<f64>) -> Option<f64> {
+    match (usage, limit) {
+        (Some(usage), Some(limit)) if limit > 0.0 => Some(usage / limit * 100.0),
+        _ => None,
+    }
+}
+
+/// Value of a quantity in base units (cores, bytes), e.g. `250m` is 0.25 and
+/// `1Gi` is 1073741824
+fn parse_quantity(quantity: &Quantity) -> Option<f64> {
+    let text = quantity.0.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '+' || c == '-'))
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier = match suffix {
+        "" => 1.0,
+        "n" => 1e-9,
+        "u" => 1e-6,
+        "m" => 1e-3,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        "P" => 1e15,
+        "E" => 1e18,
+        "Ki" => 1024.0,
+        "Mi" => 1024f64.powi(2),
+        "Gi" => 1024f64.powi(3),
+        "Ti" => 1024f64.powi(4),
+        "Pi" => 1024f64.powi(5),
+        "Ei" => 1024f64.powi(6),
+        // Decimal exponent, e.g. `12e6`
+        exponent => 10f64.powi(exponent.strip_prefix(['e', 'E'])?.parse().ok()?),
+    };
+
+    Some(number * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn quantity(text: &str) -> Option<f64> {
+        parse_quantity(&Quantity(text.to_string()))
+    }
+
+    #[test]
+    fn parses_quantities_in_base_units() {
+        assert_eq!(quantity("2"), Some(2.0));
+        assert_eq!(quantity("250m"), Some(0.25));
+        assert_eq!(quantity("1500000n"), Some(0.0015));
+        assert_eq!(quantity("1Gi"), Some(1073741824.0));
+        assert_eq!(quantity("1.5Ki"), Some(1536.0));
+        assert_eq!(quantity("128M"), Some(128e6));
+        assert_eq!(quantity("12e6"), Some(12e6));
+        assert_eq!(quantity(" 64Mi "), Some(64.0 * 1024.0 * 1024.0));
+    }
+
+    #[test]
+    fn rejects_malformed_quantities() {
+        assert_eq!(quantity(""), None);
+        assert_eq!(quantity("Gi"), None);
+        assert_eq!(quantity("5x"), None);
+        assert_eq!(quantity("1.2.3"), None);
+    }
+
+    #[test]
+    fn usage_is_compared_with_limits() {
+        let pod: Pod = serde_json::from_value(json!({
+            "metadata": { "name": "api-7d9f8-x4k2p" },
+            "spec": {
+                "containers": [
+                    {
+                        "name": "app",
+                        "resources": {
+                            "requests": { "cpu": "100m" },
+                            "limits": { "cpu": "500m", "memory": "256Mi" },
+                        },
+                    },
+                    { "name": "envoy" },
+                ],
+            },
+        }))
+        .unwrap();
+        let sample: Sample = serde_json::from_value(json!({
+            "timestamp": "2024-10-26T12:00:00Z",
+            "window": "30s",
+            "containers": [{ "name": "app", "usage": { "cpu": "125m", "memory": "128Mi" } }],
+        }))
+        .unwrap();
+
+        let metrics = pod_metrics(&pod, Some(sample));
+        assert_eq!(metrics.window.as_deref(), Some("30s"));
+
+        let app = &metrics.containers[0];
+        assert_eq!(app.cpu_millicores, Some(125.0));
+        assert_eq!(app.cpu_request_millicores, Some(100.0));
+        assert_eq!(app.cpu_percent_of_limit, Some(25.0));
+        assert_eq!(app.memory_percent_of_limit, Some(50.0));
+
+        // No sample for the sidecar and no limits to compare with
+        let envoy = &metrics.containers[1];
+        assert_eq!(envoy.cpu_millicores, None);
+        assert_eq!(envoy.memory_percent_of_limit, None);
+    }
+}
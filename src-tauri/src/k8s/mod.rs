@@ -1,6 +1,15 @@
 pub mod client;
 pub mod clusters;
+pub mod compare;
+pub mod config_watcher;
+pub mod cursor;
 pub mod deployments;
+pub mod events;
 pub mod logs;
+pub mod metrics;
 pub mod namespaces;
 pub mod pods;
+pub mod selector;
+pub mod streams;
+pub mod tasks;
+pub mod workloads;
@@ -1,17 +1,24 @@
 use k8s_openapi::api::core::v1::Namespace;
 use kube::api::ListParams;
 use kube::Api;
+use tauri::State;
 
-use crate::error::K8sError;
-use crate::k8s::client::get_client_for_context;
+use crate::error::{ErrorScope, InScope, K8sError};
 use crate::models::NamespaceInfo;
+use crate::state::AppState;
 
 #[tauri::command]
-pub async fn get_namespaces(context: String) -> Result<Vec<NamespaceInfo>, K8sError> {
-    let client = get_client_for_context(&context).await?;
+pub async fn get_namespaces(
+    state: State<'_, AppState>,
+    context: String,
+) -> Result<Vec<NamespaceInfo>, K8sError> {
+    let client = state.clients.get(&context).await?;
     let namespaces: Api<Namespace> = Api::all(client);
 
-    let ns_list = namespaces.list(&ListParams::default()).await?;
+    let ns_list = namespaces
+        .list(&ListParams::default())
+        .await
+        .in_scope(&ErrorScope::context(&context))?;
 
     let result: Vec<NamespaceInfo> = ns_list
         .items
@@ -1,105 +1,136 @@
 use chrono::Utc;
-use k8s_openapi::api::apps::v1::Deployment;
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::core::v1::{Container, ContainerStatus, Pod, PodSpec};
 use kube::api::ListParams;
 use kube::Api;
 use std::collections::HashMap;
+use tauri::State;
 
-use crate::error::K8sError;
-use crate::k8s::client::get_client_for_context;
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::selector::require_selector;
+use crate::k8s::workloads::list_workload_pods;
 use crate::models::{
-    ContainerDetails, ContainerPort, EnvVar, PodCondition, PodDetails, PodInfo,
-    ResourceRequirements,
+    ContainerDetails, ContainerKind, ContainerPort, ContainerSource, ContainerTermination, EnvVar,
+    PodCondition, PodDetails, PodInfo, ResourceRequirements, WorkloadKind,
 };
+use crate::state::AppState;
 
 #[tauri::command]
 pub async fn get_pods(
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
     deployment: Option<String>,
 ) -> Result<Vec<PodInfo>, K8sError> {
-    let client = get_client_for_context(&context).await?;
-    let pods: Api<Pod> = Api::namespaced(client.clone(), &namespace);
-
-    let list_params = if let Some(deploy_name) = deployment {
-        // Get the deployment to find its label selector
-        let deployments: Api<Deployment> = Api::namespaced(client, &namespace);
-        let deploy = deployments.get(&deploy_name).await?;
-
-        let selector = deploy
-            .spec
-            .as_ref()
-            .and_then(|s| s.selector.match_labels.as_ref())
-            .map(|labels| {
-                labels
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(",")
-            })
-            .unwrap_or_default();
-
-        ListParams::default().labels(&selector)
-    } else {
-        ListParams::default()
-    };
+    if let Some(deploy_name) = deployment {
+        return get_workload_pods(
+            state,
+            context,
+            namespace,
+            WorkloadKind::Deployment,
+            deploy_name,
+        )
+        .await;
+    }
 
-    let pod_list = pods.list(&list_params).await?;
+    let client = state.clients.get(&context).await?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
 
-    let result: Vec<PodInfo> = pod_list
-        .items
-        .iter()
-        .map(|pod| {
-            let name = pod.metadata.name.clone().unwrap_or_default();
-            let namespace = pod.metadata.namespace.clone().unwrap_or_default();
-
-            let status = get_pod_status(pod);
-            let (ready_count, total_count) = get_ready_count(pod);
-            let restarts = get_restart_count(pod);
-            let age = get_age(pod);
-            let ip = pod
-                .status
-                .as_ref()
-                .and_then(|s| s.pod_ip.clone())
-                .unwrap_or_default();
-            let node = pod
-                .spec
-                .as_ref()
-                .and_then(|s| s.node_name.clone())
-                .unwrap_or_default();
-            let containers = pod
-                .spec
-                .as_ref()
-                .map(|s| s.containers.iter().map(|c| c.name.clone()).collect())
-                .unwrap_or_default();
+    let pod_list = pods
+        .list(&ListParams::default())
+        .await
+        .in_scope(&ErrorScope::namespaced(&context, &namespace))?;
 
-            PodInfo {
-                name,
-                namespace,
-                status,
-                ready: format!("{}/{}", ready_count, total_count),
-                restarts,
-                age,
-                ip,
-                node,
-                containers,
-            }
-        })
-        .collect();
+    Ok(pod_list.items.iter().map(pod_info).collect())
+}
+
+#[tauri::command]
+pub async fn get_workload_pods(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    kind: WorkloadKind,
+    name: String,
+) -> Result<Vec<PodInfo>, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource(kind.resource(), &name);
+    let pods = list_workload_pods(&client, &namespace, kind, &name)
+        .await
+        .in_scope(&scope)?;
+
+    Ok(pods.iter().map(pod_info).collect())
+}
+
+/// List pods matching a free-form label selector such as `app=api,tier in (web,worker)`
+#[tauri::command]
+pub async fn get_pods_by_selector(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    selector: String,
+) -> Result<Vec<PodInfo>, K8sError> {
+    require_selector(&selector)?;
+    let client = state.clients.get(&context).await?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+    let pod_list = pods
+        .list(&ListParams::default().labels(&selector))
+        .await
+        .in_scope(&ErrorScope::namespaced(&context, &namespace))?;
+
+    Ok(pod_list.items.iter().map(pod_info).collect())
+}
+
+fn pod_info(pod: &Pod) -> PodInfo {
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+
+    let status = get_pod_status(pod);
+    let (ready_count, total_count) = get_ready_count(pod);
+    let restarts = get_restart_count(pod);
+    let age = get_age(pod);
+    let ip = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.pod_ip.clone())
+        .unwrap_or_default();
+    let node = pod
+        .spec
+        .as_ref()
+        .and_then(|s| s.node_name.clone())
+        .unwrap_or_default();
+    let containers = pod
+        .spec
+        .as_ref()
+        .map(|s| s.containers.iter().map(|c| c.name.clone()).collect())
+        .unwrap_or_default();
+    let container_sources = list_containers(pod);
 
-    Ok(result)
+    PodInfo {
+        name,
+        namespace,
+        status,
+        ready: format!("{}/{}", ready_count, total_count),
+        restarts,
+        age,
+        ip,
+        node,
+        containers,
+        container_sources,
+    }
 }
 
 #[tauri::command]
 pub async fn get_pod_details(
+    state: State<'_, AppState>,
     context: String,
     namespace: String,
     pod_name: String,
 ) -> Result<PodDetails, K8sError> {
-    let client = get_client_for_context(&context).await?;
+    let client = state.clients.get(&context).await?;
     let pods: Api<Pod> = Api::namespaced(client, &namespace);
 
-    let pod = pods.get(&pod_name).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("pods", &pod_name);
+    let pod = pods.get(&pod_name).await.in_scope(&scope)?;
 
     let name = pod.metadata.name.clone().unwrap_or_default();
     let ns = pod.metadata.namespace.clone().unwrap_or_default();
@@ -253,27 +284,132 @@ fn get_age(pod: &Pod) -> String {
     }
 }
 
+/// Every container of a pod that can produce logs: init containers and native
+/// sidecars (in spec order), regular containers, then ephemeral debug containers
+fn all_containers(spec: &PodSpec) -> Vec<(Container, ContainerKind)> {
+    let init = spec.init_containers.iter().flatten().map(|c| {
+        // Init containers with restartPolicy Always are native sidecars
+        let kind = if c.restart_policy.as_deref() == Some("Always") {
+            ContainerKind::Sidecar
+        } else {
+            ContainerKind::Init
+        };
+        (c.clone(), kind)
+    });
+
+    let regular = spec
+        .containers
+        .iter()
+        .map(|c| (c.clone(), ContainerKind::Regular));
+
+    // Ephemeral containers share the fields we display with regular ones
+    let ephemeral = spec.ephemeral_containers.iter().flatten().map(|c| {
+        let container = Container {
+            name: c.name.clone(),
+            image: c.image.clone(),
+            env: c.env.clone(),
+            env_from: c.env_from.clone(),
+            ports: c.ports.clone(),
+            resources: c.resources.clone(),
+            ..Default::default()
+        };
+        (container, ContainerKind::Ephemeral)
+    });
+
+    init.chain(regular).chain(ephemeral).collect()
+}
+
+/// Names and kinds of every container of a pod that can be used as a log source
+pub(crate) fn list_containers(pod: &Pod) -> Vec<ContainerSource> {
+    pod.spec
+        .as_ref()
+        .map(all_containers)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(c, kind)| ContainerSource { name: c.name, kind })
+        .collect()
+}
+
+/// Statuses of all init, regular and ephemeral containers of a pod
+fn all_container_statuses(pod: &Pod) -> impl Iterator<Item = &ContainerStatus> {
+    pod.status.iter().flat_map(|s| {
+        s.init_container_statuses
+            .iter()
+            .flatten()
+            .chain(s.container_statuses.iter().flatten())
+            .chain(s.ephemeral_container_statuses.iter().flatten())
+    })
+}
+
+/// Runtime ID of a container's current instance, which changes on each restart
+pub(crate) fn container_id(pod: &Pod, container_name: &str) -> Option<String> {
+    all_container_statuses(pod)
+        .find(|c| c.name == container_name)
+        .and_then(|c| c.container_id.clone())
+}
+
+/// Name of the workload owning a pod. Pods of a Deployment are owned by one of
+/// its ReplicaSets, so the ReplicaSet's `pod-template-hash` suffix is dropped.
+pub(crate) fn workload_name(pod: &Pod) -> Option<String> {
+    let owner = pod
+        .metadata
+        .owner_references
+        .as_ref()?
+        .iter()
+        .find(|o| o.controller == Some(true))?;
+
+    let hash = pod
+        .metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get("pod-template-hash"));
+
+    match hash {
+        Some(hash) if owner.kind == "ReplicaSet" => Some(
+            owner
+                .name
+                .strip_suffix(&format!("-{}", hash))
+                .unwrap_or(&owner.name)
+                .to_string(),
+        ),
+        _ => Some(owner.name.clone()),
+    }
+}
+
+/// Whether a container's current instance is running, i.e. may log more
+pub(crate) fn is_running(pod: &Pod, container_name: &str) -> bool {
+    all_container_statuses(pod)
+        .find(|c| c.name == container_name)
+        .and_then(|c| c.state.as_ref())
+        .is_some_and(|s| s.running.is_some())
+}
+
+/// Whether a container has a previous instance whose logs can be fetched
+pub(crate) fn has_previous_instance(pod: &Pod, container_name: &str) -> bool {
+    all_container_statuses(pod)
+        .find(|c| c.name == container_name)
+        .map(|c| {
+            c.restart_count > 0
+                || c.last_state
+                    .as_ref()
+                    .is_some_and(|s| s.terminated.is_some())
+        })
+        .unwrap_or(false)
+}
+
 fn get_container_details(pod: &Pod) -> Vec<ContainerDetails> {
     let spec = match &pod.spec {
         Some(s) => s,
         None => return vec![],
     };
 
-    let status_map: HashMap<String, _> = pod
-        .status
-        .as_ref()
-        .and_then(|s| s.container_statuses.as_ref())
-        .map(|statuses| {
-            statuses
-                .iter()
-                .map(|cs| (cs.name.clone(), cs.clone()))
-                .collect()
-        })
-        .unwrap_or_default();
+    let status_map: HashMap<String, _> = all_container_statuses(pod)
+        .map(|cs| (cs.name.clone(), cs.clone()))
+        .collect();
 
-    spec.containers
+    all_containers(spec)
         .iter()
-        .map(|container| {
+        .map(|(container, kind)| {
             let cs = status_map.get(&container.name);
 
             let state = cs
@@ -294,6 +430,18 @@ fn get_container_details(pod: &Pod) -> Vec<ContainerDetails> {
                 })
                 .unwrap_or_else(|| "Unknown".to_string());
 
+            let last_state = cs
+                .and_then(|s| s.last_state.as_ref())
+                .and_then(|s| s.terminated.as_ref())
+                .map(|t| ContainerTermination {
+                    exit_code: t.exit_code,
+                    reason: t.reason.clone(),
+                    signal: t.signal,
+                    message: t.message.clone(),
+                    started_at: t.started_at.as_ref().map(|t| t.0.to_rfc3339()),
+                    finished_at: t.finished_at.as_ref().map(|t| t.0.to_rfc3339()),
+                });
+
             // Collect env vars from 'env' field
             let mut env_vars: Vec<EnvVar> = container
                 .env
@@ -388,10 +536,12 @@ fn get_container_details(pod: &Pod) -> Vec<ContainerDetails> {
 
             ContainerDetails {
                 name: container.name.clone(),
+                kind: *kind,
                 image: container.image.clone().unwrap_or_default(),
                 ready: cs.map(|s| s.ready).unwrap_or(false),
                 restart_count: cs.map(|s| s.restart_count).unwrap_or(0),
                 state,
+                last_state,
                 env_vars,
                 ports,
                 resources,
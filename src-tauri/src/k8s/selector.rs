@@ -0,0 +1,137 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+
+use crate::error::K8sError;
+
+/// Render a `LabelSelector` in the string form the API server accepts for
+/// `labelSelector`, including `matchExpressions`:
+///
+/// - `In` becomes `key in (a,b)`
+/// - `NotIn` becomes `key notin (a,b)`
+/// - `Exists` becomes `key`
+/// - `DoesNotExist` becomes `!key`
+///
+/// An empty selector renders as an empty string, which matches everything.
+pub fn to_selector_string(selector: &LabelSelector) -> Result<String, K8sError> {
+    let mut requirements: Vec<String> = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+
+    for expr in selector.match_expressions.iter().flatten() {
+        let values = expr.values.as_deref().unwrap_or_default();
+
+        let requirement = match expr.operator.as_str() {
+            "In" | "NotIn" if values.is_empty() => {
+                return Err(K8sError::InvalidSelector(format!(
+                    "operator {} on '{}' requires at least one value",
+                    expr.operator, expr.key
+                )));
+            }
+            "In" => format!("{} in ({})", expr.key, values.join(",")),
+            "NotIn" => format!("{} notin ({})", expr.key, values.join(",")),
+            "Exists" => expr.key.clone(),
+            "DoesNotExist" => format!("!{}", expr.key),
+            other => {
+                return Err(K8sError::InvalidSelector(format!(
+                    "unknown operator '{}' on '{}'",
+                    other, expr.key
+                )));
+            }
+        };
+
+        requirements.push(requirement);
+    }
+
+    Ok(requirements.join(","))
+}
+
+/// Reject an empty selector, which the API server treats as matching every
+/// pod of the namespace
+pub fn require_selector(selector: &str) -> Result<(), K8sError> {
+    if selector.trim().is_empty() {
+        return Err(K8sError::InvalidSelector(
+            "selector is empty and would match every pod in the namespace".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelectorRequirement;
+    use std::collections::BTreeMap;
+
+    fn requirement(key: &str, operator: &str, values: &[&str]) -> LabelSelectorRequirement {
+        LabelSelectorRequirement {
+            key: key.to_string(),
+            operator: operator.to_string(),
+            values: (!values.is_empty()).then(|| values.iter().map(|v| v.to_string()).collect()),
+        }
+    }
+
+    fn selector(
+        labels: &[(&str, &str)],
+        expressions: Vec<LabelSelectorRequirement>,
+    ) -> LabelSelector {
+        LabelSelector {
+            match_labels: Some(
+                labels
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            match_expressions: Some(expressions),
+        }
+    }
+
+    #[test]
+    fn renders_labels_then_set_based_requirements() {
+        let selector = selector(
+            &[("app", "api")],
+            vec![
+                requirement("tier", "In", &["web", "worker"]),
+                requirement("env", "NotIn", &["dev"]),
+                requirement("canary", "Exists", &[]),
+                requirement("legacy", "DoesNotExist", &[]),
+            ],
+        );
+        assert_eq!(
+            to_selector_string(&selector).unwrap(),
+            "app=api,tier in (web,worker),env notin (dev),canary,!legacy"
+        );
+    }
+
+    #[test]
+    fn empty_selector_matches_everything() {
+        assert_eq!(to_selector_string(&LabelSelector::default()).unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_invalid_requirements() {
+        let empty_in = selector(&[], vec![requirement("tier", "In", &[])]);
+        assert!(matches!(
+            to_selector_string(&empty_in),
+            Err(K8sError::InvalidSelector(_))
+        ));
+
+        let unknown = selector(&[], vec![requirement("tier", "Gt", &["1"])]);
+        assert!(matches!(
+            to_selector_string(&unknown),
+            Err(K8sError::InvalidSelector(_))
+        ));
+    }
+
+    #[test]
+    fn blank_selectors_are_rejected() {
+        assert!(require_selector("app=api").is_ok());
+        for blank in ["", "  ", "\t"] {
+            assert!(matches!(
+                require_selector(blank),
+                Err(K8sError::InvalidSelector(_))
+            ));
+        }
+    }
+}
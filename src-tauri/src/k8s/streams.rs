@@ -0,0 +1,851 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::{AsyncBufReadExt, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::LogParams;
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::{Api, Client};
+use tauri::ipc::Channel;
+use tauri::State;
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::Instant;
+
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::cursor::{CursorTracker, LogCursor};
+use crate::k8s::events::{event_entry, follow_events, interleave, EventFeed, EventFilter};
+use crate::k8s::logs::{parse_log_line, MultilineGrouper};
+use crate::k8s::pods::{container_id, list_containers};
+use crate::k8s::selector::{require_selector, to_selector_string};
+use crate::k8s::workloads::resolve_selectors;
+use crate::models::{EventTarget, LogEntry, LogStreamEvent, LogStreamOptions, WorkloadKind};
+use crate::parser::{LineParser, ParseConfig};
+use crate::state::AppState;
+
+/// Maximum number of lines sent to the webview in a single batch
+const STREAM_BATCH_SIZE: usize = 500;
+
+/// How long a merged stream waits on a quiet container before sending the
+/// other containers' lines without it
+const MERGE_WINDOW_MS: u64 = 250;
+
+/// How long a stream waits for more continuation lines before sending an entry
+const MULTILINE_IDLE_MS: u64 = 200;
+
+/// Start following a pod's logs, pushing parsed entries to `on_event` until
+/// the stream ends or `stop_log_stream` is called with the same id.
+#[tauri::command]
+pub async fn start_log_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+    context: String,
+    namespace: String,
+    pod_name: String,
+    options: LogStreamOptions,
+    on_event: Channel<LogStreamEvent>,
+) -> Result<(), K8sError> {
+    let client = state.clients.get(&context).await?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("pods", &pod_name);
+
+    // Resolve the default container the same way get_pod_logs does
+    let pod = pods.get(&pod_name).await.in_scope(&scope)?;
+    let container_name = match options.container {
+        Some(c) => c,
+        None => pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.containers.first())
+            .map(|c| c.name.clone())
+            .unwrap_or_default(),
+    };
+
+    let config = ParseConfig::load(&state.settings, options.multiline.as_ref())?;
+    let parser = config.parser_for(&pod, &container_name);
+    let params = LogParams {
+        follow: true,
+        timestamps: true,
+        container: Some(container_name.clone()),
+        tail_lines: options.tail_lines,
+        ..Default::default()
+    };
+
+    let feed = match options.include_events {
+        Some(true) => {
+            let target = EventTarget::Pod {
+                name: pod_name.clone(),
+            };
+            Some(
+                EventFeed::load(&client, &namespace, target)
+                    .await
+                    .in_scope(&scope)?,
+            )
+        }
+        _ => None,
+    };
+
+    // Open the stream up front so connection errors surface to the caller
+    let follow: Api<Pod> =
+        Api::namespaced(state.clients.get_for_follow(&context).await?, &namespace);
+    let reader = follow
+        .log_stream(&pod_name, &params)
+        .await
+        .in_scope(&scope)?;
+
+    let idle = Duration::from_millis(MULTILINE_IDLE_MS);
+    let handle = tokio::spawn(async move {
+        let mut batches = reader.lines().ready_chunks(STREAM_BATCH_SIZE);
+        let mut grouper = MultilineGrouper::new(&parser.multiline);
+
+        // Past events go out with the first entries, new ones as they happen
+        let (mut history, _follower) = match feed {
+            Some(feed) => {
+                let sender = on_event.clone();
+                let follower = spawn_event_follower(
+                    feed.filter,
+                    scope.clone(),
+                    on_event.clone(),
+                    move |entry| sender.send(LogStreamEvent::Entries(vec![entry])).is_ok(),
+                );
+                (
+                    feed.history.into_iter().map(event_entry).collect(),
+                    Some(follower),
+                )
+            }
+            None => (Vec::new(), None),
+        };
+        let mut send = |entries: Vec<LogEntry>| {
+            if entries.is_empty() {
+                return true;
+            }
+            let entries = interleave(entries, std::mem::take(&mut history));
+            on_event.send(LogStreamEvent::Entries(entries)).is_ok()
+        };
+
+        loop {
+            let batch = if grouper.has_pending() {
+                match tokio::time::timeout(idle, batches.next()).await {
+                    Ok(batch) => batch,
+                    // Nothing more arrived, so the held back entry is complete
+                    Err(_) => {
+                        if !send(grouper.flush().into_iter().collect()) {
+                            return;
+                        }
+                        continue;
+                    }
+                }
+            } else {
+                batches.next().await
+            };
+            let Some(batch) = batch else {
+                break;
+            };
+
+            let mut entries: Vec<LogEntry> = Vec::with_capacity(batch.len());
+            let mut failure = None;
+
+            for line in batch {
+                match line {
+                    Ok(line) => entries.extend(grouper.push(parse_log_line(&line, &parser))),
+                    Err(e) => {
+                        failure = Some(K8sError::from(e));
+                        break;
+                    }
+                }
+            }
+
+            if failure.is_some() {
+                entries.extend(grouper.flush());
+            }
+
+            if !send(entries) {
+                // Webview is gone, nobody is listening anymore
+                return;
+            }
+
+            if let Some(error) = failure {
+                let _ = on_event.send(LogStreamEvent::Error(error.with_scope(&scope).into()));
+                return;
+            }
+        }
+
+        send(grouper.flush().into_iter().collect());
+        // Events of a pod that logged nothing
+        if !history.is_empty() {
+            let _ = on_event.send(LogStreamEvent::Entries(history));
+        }
+        let _ = on_event.send(LogStreamEvent::Ended);
+    });
+
+    state.tasks.track(&stream_id, handle);
+
+    Ok(())
+}
+
+/// Stop a stream started with `start_log_stream`
+#[tauri::command]
+pub async fn stop_log_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+) -> Result<bool, K8sError> {
+    Ok(state.tasks.cancel(&stream_id))
+}
+
+/// Follow the logs of every pod in a deployment as one feed ordered by
+/// timestamp. Pods are attached and detached as the deployment rolls.
+#[tauri::command]
+pub async fn start_deployment_log_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+    context: String,
+    namespace: String,
+    deployment: String,
+    options: LogStreamOptions,
+    on_event: Channel<LogStreamEvent>,
+) -> Result<(), K8sError> {
+    let client = state.clients.get(&context).await?;
+
+    let scope = ErrorScope::namespaced(&context, &namespace).resource("deployments", &deployment);
+
+    let selectors = resolve_selectors(&client, &namespace, WorkloadKind::Deployment, &deployment)
+        .await
+        .in_scope(&scope)?;
+    let selector = match selectors.first() {
+        Some(selector) => to_selector_string(selector).in_scope(&scope)?,
+        None => {
+            let error = format!("deployment {} has no pod selector", deployment);
+            return Err(K8sError::InvalidSelector(error).with_scope(&scope));
+        }
+    };
+    // Following the whole namespace instead would be a surprise
+    require_selector(&selector).in_scope(&scope)?;
+
+    let feed = match options.include_events {
+        Some(true) => {
+            let target = EventTarget::Deployment { name: deployment };
+            Some(
+                EventFeed::load(&client, &namespace, target)
+                    .await
+                    .in_scope(&scope)?,
+            )
+        }
+        _ => None,
+    };
+
+    let pods = MergedPods::new(&state, &context, &namespace, client).await?;
+    let config = ParseConfig::load(&state.settings, options.multiline.as_ref())?;
+    let handle = spawn_merged_stream(pods, selector, options, config, feed, on_event);
+    state.tasks.track(&stream_id, handle);
+
+    Ok(())
+}
+
+/// Follow the logs of every pod matching a free-form label selector as one
+/// merged feed, like `start_deployment_log_stream`
+#[tauri::command]
+pub async fn start_selector_log_stream(
+    state: State<'_, AppState>,
+    stream_id: String,
+    context: String,
+    namespace: String,
+    selector: String,
+    options: LogStreamOptions,
+    on_event: Channel<LogStreamEvent>,
+) -> Result<(), K8sError> {
+    require_selector(&selector)?;
+    let client = state.clients.get(&context).await?;
+    let pods = MergedPods::new(&state, &context, &namespace, client).await?;
+    let config = ParseConfig::load(&state.settings, options.multiline.as_ref())?;
+    let handle = spawn_merged_stream(pods, selector, options, config, None, on_event);
+    state.tasks.track(&stream_id, handle);
+
+    Ok(())
+}
+
+/// Pods of a merged stream, watched with the regular client and followed with
+/// the one without a read timeout
+struct MergedPods {
+    watched: Api<Pod>,
+    followed: Api<Pod>,
+    /// Context and namespace, for the errors reported along the stream
+    scope: ErrorScope,
+}
+
+impl MergedPods {
+    async fn new(
+        state: &AppState,
+        context: &str,
+        namespace: &str,
+        client: Client,
+    ) -> Result<Self, K8sError> {
+        let follow_client = state.clients.get_for_follow(context).await?;
+        Ok(Self {
+            watched: Api::namespaced(client, namespace),
+            followed: Api::namespaced(follow_client, namespace),
+            scope: ErrorScope::namespaced(context, namespace),
+        })
+    }
+}
+
+/// Spawn the task behind a merged stream: watch pods matching `selector`,
+/// follow each of their containers (and the events of `feed`), and send the
+/// interleaved lines in timestamp order
+fn spawn_merged_stream(
+    pods: MergedPods,
+    selector: String,
+    options: LogStreamOptions,
+    config: ParseConfig,
+    feed: Option<EventFeed>,
+    on_event: Channel<LogStreamEvent>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Followed>();
+        let mut followers = Followers {
+            pods: pods.followed,
+            tx: tx.clone(),
+            next_source: EVENT_SOURCE + 1,
+        };
+        let scope = pods.scope;
+        let mut attached = AttachedPods::default();
+        let mut merger = Merger::default();
+        let mut initial_sync = true;
+
+        // Past events are merged in once the first lines arrive
+        let mut history: Vec<LogEntry> = Vec::new();
+        let _follower = feed.map(|feed| {
+            history.extend(feed.history.into_iter().map(event_entry));
+            merger.add(EVENT_SOURCE, false);
+            let tx = tx.clone();
+            spawn_event_follower(feed.filter, scope.clone(), on_event.clone(), move |entry| {
+                tx.send(Followed::Entry(EVENT_SOURCE, entry)).is_ok()
+            })
+        });
+
+        let mut events = watcher(pods.watched, watcher::Config::default().labels(&selector))
+            .default_backoff()
+            .boxed();
+        let idle = Duration::from_millis(MERGE_WINDOW_MS);
+        let mut tick = tokio::time::interval(idle);
+
+        loop {
+            tokio::select! {
+                event = events.next() => {
+                    let event = match event {
+                        Some(Ok(event)) => event,
+                        Some(Err(e)) => {
+                            // The watcher backs off and retries on its own
+                            let error = K8sError::from(e).with_scope(&scope);
+                            let _ = on_event.send(LogStreamEvent::Error(error.into()));
+                            continue;
+                        }
+                        None => break,
+                    };
+
+                    match event {
+                        watcher::Event::InitDone => initial_sync = false,
+                        watcher::Event::Init => {}
+                        watcher::Event::Apply(pod) | watcher::Event::InitApply(pod) => {
+                            let pod_name = pod.metadata.name.clone().unwrap_or_default();
+                            if !has_started(&pod) {
+                                continue;
+                            }
+                            let new_pod = !attached.contains(&pod_name);
+
+                            // Pods that exist when the stream starts get the requested tail,
+                            // pods created later and restarted containers are followed from
+                            // their first line
+                            let tail_lines = if initial_sync && new_pod {
+                                options.tail_lines
+                            } else {
+                                None
+                            };
+                            for c in list_containers(&pod) {
+                                let id = container_id(&pod, &c.name);
+                                if !attached.is_new_instance(&pod_name, &c.name, id.as_deref()) {
+                                    continue;
+                                }
+                                let follower = followers.spawn(
+                                    &mut merger,
+                                    config.parser_for(&pod, &c.name),
+                                    tail_lines,
+                                    None,
+                                );
+                                attached.insert(&pod_name, c.name, id, follower);
+                            }
+
+                            if new_pod
+                                && on_event.send(LogStreamEvent::PodAttached(pod_name)).is_err()
+                            {
+                                return;
+                            }
+                        }
+                        watcher::Event::Delete(pod) => {
+                            let pod_name = pod.metadata.name.clone().unwrap_or_default();
+                            let Some(sources) = attached.remove(&pod_name) else {
+                                continue;
+                            };
+                            sources.into_iter().for_each(|source| merger.end(source));
+                            if on_event.send(LogStreamEvent::PodDetached(pod_name)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                Some(followed) = rx.recv() => match followed {
+                    Followed::Entry(source, entry) => {
+                        if source != EVENT_SOURCE && !history.is_empty() {
+                            for event in std::mem::take(&mut history) {
+                                merger.push(EVENT_SOURCE, event);
+                            }
+                        }
+                        merger.push(source, entry);
+                    }
+                    Followed::Ended(source) => merger.end(source),
+                    Followed::Failed { source, parser, resume, tail_lines, error } => {
+                        merger.end(source);
+                        let pod_scope = scope.clone().resource("pods", &parser.pod_name);
+                        let error = error.with_scope(&pod_scope);
+                        if on_event.send(LogStreamEvent::Error(error.into())).is_err() {
+                            return;
+                        }
+
+                        // Pick up after the last line received, unless the pod is gone
+                        // or its container restarted and has a new follower already
+                        let (pod_name, container) =
+                            (parser.pod_name.clone(), parser.container_name.clone());
+                        if attached.is_followed_by(&pod_name, &container, source) {
+                            let follower = followers.spawn(&mut merger, parser, tail_lines, resume);
+                            attached.replace_follower(&pod_name, &container, follower);
+                        }
+                    }
+                },
+                _ = tick.tick() => {}
+            }
+
+            if !send_merged(&on_event, merger.drain(idle)) {
+                return;
+            }
+        }
+
+        send_merged(&on_event, merger.drain(Duration::ZERO));
+        let _ = on_event.send(LogStreamEvent::Ended);
+    })
+}
+
+/// Send merged entries in batches. False once the webview stopped listening.
+fn send_merged(on_event: &Channel<LogStreamEvent>, entries: Vec<LogEntry>) -> bool {
+    entries.chunks(STREAM_BATCH_SIZE).all(|chunk| {
+        on_event
+            .send(LogStreamEvent::Entries(chunk.to_vec()))
+            .is_ok()
+    })
+}
+
+/// Merge source of the event follower. Containers get the ids after it.
+const EVENT_SOURCE: usize = 0;
+
+/// Messages from the followers of a merged stream to the merging task
+enum Followed {
+    Entry(usize, LogEntry),
+    /// The container's stream ended along with its instance
+    Ended(usize),
+    /// Reading the container's stream failed
+    Failed {
+        source: usize,
+        parser: LineParser,
+        /// Where the stream stopped, to resume after
+        resume: Option<LogCursor>,
+        /// Tail to start over with when nothing was read yet
+        tail_lines: Option<i64>,
+        error: K8sError,
+    },
+}
+
+/// Spawns container followers, each with its own merge source
+struct Followers {
+    pods: Api<Pod>,
+    tx: mpsc::UnboundedSender<Followed>,
+    next_source: usize,
+}
+
+impl Followers {
+    /// Follow a container from its last `tail_lines`, or from after `resume`
+    fn spawn(
+        &mut self,
+        merger: &mut Merger,
+        parser: LineParser,
+        tail_lines: Option<i64>,
+        resume: Option<LogCursor>,
+    ) -> Follower {
+        let source = self.next_source;
+        self.next_source += 1;
+        merger.add(source, true);
+
+        let handle = tokio::spawn(pump_container_logs(
+            self.pods.clone(),
+            parser,
+            tail_lines,
+            resume,
+            source,
+            self.tx.clone(),
+        ))
+        .abort_handle();
+        Follower { source, handle }
+    }
+}
+
+/// K-way merge of the sources of a merged stream by `timestamp_utc`. Each
+/// source is ordered already, so the oldest head is next, as long as every
+/// source has a head to compare. A source that has had nothing for a while
+/// stops being waited for, which bounds how long quiet pods hold up the rest.
+#[derive(Default)]
+struct Merger {
+    sources: HashMap<usize, MergeSource>,
+}
+
+struct MergeSource {
+    queue: VecDeque<LogEntry>,
+    /// Last time an entry came in (or the source was added)
+    last_active: Instant,
+    /// Whether the merge waits for this source when it has no head. Events
+    /// are too rare to wait for.
+    awaited: bool,
+    ended: bool,
+}
+
+impl Merger {
+    fn add(&mut self, source: usize, awaited: bool) {
+        self.sources.insert(
+            source,
+            MergeSource {
+                queue: VecDeque::new(),
+                last_active: Instant::now(),
+                awaited,
+                ended: false,
+            },
+        );
+    }
+
+    fn push(&mut self, source: usize, entry: LogEntry) {
+        // Lines of a follower that ended since still go out
+        let source = self.sources.entry(source).or_insert_with(|| MergeSource {
+            queue: VecDeque::new(),
+            last_active: Instant::now(),
+            awaited: false,
+            ended: true,
+        });
+        source.queue.push_back(entry);
+        source.last_active = Instant::now();
+    }
+
+    /// No more entries will come from `source`
+    fn end(&mut self, source: usize) {
+        if let Some(source) = self.sources.get_mut(&source) {
+            source.ended = true;
+        }
+    }
+
+    /// Take the entries that can go out in order: the oldest head, for as long
+    /// as no live source that was active within `idle` is empty
+    fn drain(&mut self, idle: Duration) -> Vec<LogEntry> {
+        let mut merged = Vec::new();
+        let now = Instant::now();
+
+        loop {
+            self.sources
+                .retain(|_, source| !(source.ended && source.queue.is_empty()));
+
+            let waiting = self.sources.values().any(|source| {
+                source.awaited
+                    && !source.ended
+                    && source.queue.is_empty()
+                    && now.duration_since(source.last_active) < idle
+            });
+            if waiting {
+                break;
+            }
+
+            let oldest = self
+                .sources
+                .values_mut()
+                .filter(|source| !source.queue.is_empty())
+                .min_by(|a, b| a.queue[0].timestamp_utc.cmp(&b.queue[0].timestamp_utc));
+            match oldest.and_then(|source| source.queue.pop_front()) {
+                Some(entry) => merged.push(entry),
+                None => break,
+            }
+        }
+
+        merged
+    }
+}
+
+/// Follow the new events of a feed, handing each to `send_entry` as a log
+/// entry until it returns false. Watch errors are reported within `scope`.
+fn spawn_event_follower<F>(
+    filter: EventFilter,
+    scope: ErrorScope,
+    on_event: Channel<LogStreamEvent>,
+    mut send_entry: F,
+) -> AbortOnDrop
+where
+    F: FnMut(LogEntry) -> bool + Send + 'static,
+{
+    let handle = tokio::spawn(follow_events(filter, move |result| match result {
+        Ok(event) => send_entry(event_entry(event)),
+        Err(e) => on_event
+            .send(LogStreamEvent::Error(e.with_scope(&scope).into()))
+            .is_ok(),
+    }));
+    AbortOnDrop(handle.abort_handle())
+}
+
+/// Aborts a helper task once the stream owning it ends or is stopped
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Per-pod log followers of a merged stream, one per container. Dropping this
+/// aborts them all, which is what happens when the owning stream is stopped.
+#[derive(Default)]
+struct AttachedPods(HashMap<String, HashMap<String, AttachedContainer>>);
+
+struct AttachedContainer {
+    /// Container ID of the instance being followed, if it had started
+    instance: Option<String>,
+    follower: Follower,
+}
+
+/// Task following one container, and the merge source it feeds
+struct Follower {
+    source: usize,
+    handle: AbortHandle,
+}
+
+impl AttachedPods {
+    fn contains(&self, pod_name: &str) -> bool {
+        self.0.contains_key(pod_name)
+    }
+
+    /// Whether a container of an attached pod runs an instance that isn't
+    /// followed yet: it was added to the pod, started late or restarted. The
+    /// log stream of an instance ends with it, so each one needs a follower.
+    fn is_new_instance(&self, pod_name: &str, container: &str, instance: Option<&str>) -> bool {
+        match self.0.get(pod_name).and_then(|c| c.get(container)) {
+            Some(attached) => instance.is_some() && attached.instance.as_deref() != instance,
+            None => true,
+        }
+    }
+
+    /// Whether the follower feeding `source` is still the one of the container
+    fn is_followed_by(&self, pod_name: &str, container: &str, source: usize) -> bool {
+        self.0
+            .get(pod_name)
+            .and_then(|c| c.get(container))
+            .is_some_and(|attached| attached.follower.source == source)
+    }
+
+    /// Record a container's follower. A follower it replaces is left to end
+    /// with its instance's stream, so that instance's last lines aren't lost.
+    fn insert(
+        &mut self,
+        pod_name: &str,
+        container: String,
+        instance: Option<String>,
+        follower: Follower,
+    ) {
+        self.0
+            .entry(pod_name.to_string())
+            .or_default()
+            .insert(container, AttachedContainer { instance, follower });
+    }
+
+    /// Swap the follower of the same instance of a container, after the
+    /// previous one failed
+    fn replace_follower(&mut self, pod_name: &str, container: &str, follower: Follower) {
+        if let Some(attached) = self.0.get_mut(pod_name).and_then(|c| c.get_mut(container)) {
+            attached.follower = follower;
+        }
+    }
+
+    /// Detach a pod, returning the merge sources of its followers
+    fn remove(&mut self, pod_name: &str) -> Option<Vec<usize>> {
+        let containers = self.0.remove(pod_name)?;
+        Some(
+            containers
+                .values()
+                .map(|c| {
+                    c.follower.handle.abort();
+                    c.follower.source
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Drop for AttachedPods {
+    fn drop(&mut self) {
+        self.0
+            .values()
+            .flat_map(HashMap::values)
+            .for_each(|c| c.follower.handle.abort());
+    }
+}
+
+/// Whether the pod's containers have started, i.e. have logs to follow
+fn has_started(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.phase.as_deref())
+        .map(|phase| phase != "Pending")
+        .unwrap_or(false)
+}
+
+/// Follow one container and forward its parsed lines to the merger. A
+/// follower resuming after a failed one starts from the `resume` cursor
+/// instead of `tail_lines`.
+async fn pump_container_logs(
+    pods: Api<Pod>,
+    parser: LineParser,
+    tail_lines: Option<i64>,
+    resume: Option<LogCursor>,
+    source: usize,
+    tx: mpsc::UnboundedSender<Followed>,
+) {
+    let params = LogParams {
+        follow: true,
+        timestamps: true,
+        container: Some(parser.container_name.clone()),
+        // The API truncates this to the second; the tracker drops the lines
+        // already sent from that second
+        since_time: resume.as_ref().map(LogCursor::since),
+        tail_lines: if resume.is_some() { None } else { tail_lines },
+        ..Default::default()
+    };
+
+    let Ok(reader) = pods.log_stream(&parser.pod_name, &params).await else {
+        // Skip containers we can't get logs from
+        let _ = tx.send(Followed::Ended(source));
+        return;
+    };
+
+    let mut lines = reader.lines();
+    let mut tracker = CursorTracker::new(resume);
+    let mut grouper = MultilineGrouper::new(&parser.multiline);
+    let idle = Duration::from_millis(MULTILINE_IDLE_MS);
+    let send = |entry: Option<LogEntry>| match entry {
+        Some(entry) => tx.send(Followed::Entry(source, entry)).is_ok(),
+        None => true,
+    };
+
+    let failure = loop {
+        let line = if grouper.has_pending() {
+            match tokio::time::timeout(idle, lines.next()).await {
+                Ok(line) => line,
+                Err(_) => {
+                    if !send(grouper.flush()) {
+                        return;
+                    }
+                    continue;
+                }
+            }
+        } else {
+            lines.next().await
+        };
+
+        match line {
+            Some(Ok(line)) => {
+                if tracker.admit(&line) && !send(grouper.push(parse_log_line(&line, &parser))) {
+                    return;
+                }
+            }
+            Some(Err(e)) => break Some(K8sError::from(e)),
+            None => break None,
+        }
+    };
+
+    if !send(grouper.flush()) {
+        return;
+    }
+    let _ = tx.send(match failure {
+        Some(error) => Followed::Failed {
+            source,
+            resume: tracker.position().cloned(),
+            tail_lines,
+            parser,
+            error,
+        },
+        None => Followed::Ended(source),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(message: &str, second: u32) -> LogEntry {
+        LogEntry {
+            timestamp_utc: Some(format!("2024-10-26T12:00:{:02}.000000000Z", second)),
+            ..LogEntry::from_message(message)
+        }
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    const IDLE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn merges_sources_by_timestamp() {
+        let mut merger = Merger::default();
+        merger.add(1, true);
+        merger.add(2, true);
+
+        merger.push(1, at("a1", 1));
+        merger.push(1, at("a3", 3));
+        // Source 2 may still send something older than a1
+        assert!(merger.drain(IDLE).is_empty());
+
+        merger.push(2, at("b2", 2));
+        assert_eq!(messages(&merger.drain(IDLE)), ["a1", "b2"]);
+
+        merger.push(2, at("b4", 4));
+        assert_eq!(messages(&merger.drain(IDLE)), ["a3"]);
+    }
+
+    #[test]
+    fn quiet_and_ended_sources_are_not_waited_for() {
+        let mut merger = Merger::default();
+        merger.add(1, true);
+        merger.add(2, true);
+        merger.add(EVENT_SOURCE, false);
+
+        merger.push(1, at("a1", 1));
+        merger.push(EVENT_SOURCE, at("event", 0));
+        assert!(merger.drain(IDLE).is_empty());
+        // Source 2 has been quiet for longer than the idle bound
+        assert_eq!(messages(&merger.drain(Duration::ZERO)), ["event", "a1"]);
+
+        merger.push(1, at("a2", 2));
+        merger.end(2);
+        assert_eq!(messages(&merger.drain(IDLE)), ["a2"]);
+    }
+
+    #[test]
+    fn lines_of_ended_sources_still_go_out() {
+        let mut merger = Merger::default();
+        merger.add(1, true);
+        merger.push(1, at("a1", 1));
+        merger.end(1);
+        merger.push(1, at("a2", 2));
+
+        assert_eq!(messages(&merger.drain(IDLE)), ["a1", "a2"]);
+        assert!(merger.sources.is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::error::K8sError;
+
+/// Registry of long-running background tasks (log streams, searches) that
+/// the UI can cancel by id. Cheap to clone; clones share the same registry.
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: Arc<Mutex<HashMap<String, (u64, AbortHandle)>>>,
+    next_generation: Arc<Mutex<u64>>,
+}
+
+impl TaskRegistry {
+    /// Register a task under `id`, aborting any task previously registered
+    /// under the same id. Returns a generation token for `finish`.
+    pub fn register(&self, id: &str, handle: AbortHandle) -> u64 {
+        let generation = {
+            let mut next = self.next_generation.lock().unwrap();
+            *next += 1;
+            *next
+        };
+
+        let previous = self
+            .tasks
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), (generation, handle));
+
+        if let Some((_, old)) = previous {
+            old.abort();
+        }
+
+        generation
+    }
+
+    /// Register a spawned task and unregister it again once it completes
+    pub fn track<T: Send + 'static>(&self, id: &str, handle: JoinHandle<T>) {
+        let generation = self.register(id, handle.abort_handle());
+        let registry = self.clone();
+        let id = id.to_string();
+
+        tokio::spawn(async move {
+            let _ = handle.await;
+            registry.finish(&id, generation);
+        });
+    }
+
+    /// Abort the task registered under `id`. Returns false if no such task exists.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tasks.lock().unwrap().remove(id) {
+            Some((_, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a task that completed on its own. The generation check keeps a
+    /// finished task from unregistering a newer task that reused its id.
+    pub fn finish(&self, id: &str, generation: u64) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.get(id).map(|(g, _)| *g) == Some(generation) {
+            tasks.remove(id);
+        }
+    }
+
+    /// Run `future` as a task that can be cancelled under `id` (if given) and
+    /// wait for its result. Cancellation yields `K8sError::Cancelled`.
+    pub async fn run_cancellable<F>(
+        &self,
+        id: Option<&str>,
+        future: F,
+    ) -> Result<F::Output, K8sError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = tokio::spawn(future);
+        let generation = id.map(|id| self.register(id, handle.abort_handle()));
+
+        let outcome = handle.await;
+
+        if let (Some(id), Some(generation)) = (id, generation) {
+            self.finish(id, generation);
+        }
+
+        match outcome {
+            Ok(output) => Ok(output),
+            Err(e) if e.is_cancelled() => Err(K8sError::Cancelled),
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+use kube::api::ListParams;
+use kube::{Api, Client, Resource};
+use tauri::State;
+
+use crate::error::{ErrorScope, InScope, K8sError};
+use crate::k8s::selector::to_selector_string;
+use crate::models::{DeploymentCondition, WorkloadDetails, WorkloadInfo, WorkloadKind};
+use crate::state::AppState;
+
+/// Convert a workload's condition list into `DeploymentCondition`s. The
+/// condition types of all workload kinds share these fields except the
+/// update/probe time, which only Deployments and Jobs have.
+macro_rules! conditions {
+    ($conds:expr, $update_time:ident) => {
+        $conds
+            .into_iter()
+            .flatten()
+            .map(|c| DeploymentCondition {
+                condition_type: c.type_.clone(),
+                status: c.status.clone(),
+                reason: c.reason.clone(),
+                message: c.message.clone(),
+                last_update_time: c.$update_time.as_ref().map(|t| t.0.to_rfc3339()),
+                last_transition_time: c.last_transition_time.as_ref().map(|t| t.0.to_rfc3339()),
+            })
+            .collect()
+    };
+    ($conds:expr) => {
+        $conds
+            .into_iter()
+            .flatten()
+            .map(|c| DeploymentCondition {
+                condition_type: c.type_.clone(),
+                status: c.status.clone(),
+                reason: c.reason.clone(),
+                message: c.message.clone(),
+                last_update_time: None,
+                last_transition_time: c.last_transition_time.as_ref().map(|t| t.0.to_rfc3339()),
+            })
+            .collect()
+    };
+}
+
+#[tauri::command]
+pub async fn get_workloads(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    kind: WorkloadKind,
+) -> Result<Vec<WorkloadInfo>, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace);
+
+    let result = match kind {
+        WorkloadKind::Deployment => list_infos(&client, &namespace, kind, deployment_counts)
+            .await
+            .in_scope(&scope)?,
+        WorkloadKind::StatefulSet => list_infos(&client, &namespace, kind, stateful_set_counts)
+            .await
+            .in_scope(&scope)?,
+        WorkloadKind::DaemonSet => list_infos(&client, &namespace, kind, daemon_set_counts)
+            .await
+            .in_scope(&scope)?,
+        WorkloadKind::ReplicaSet => list_infos(&client, &namespace, kind, replica_set_counts)
+            .await
+            .in_scope(&scope)?,
+        WorkloadKind::Job => list_infos(&client, &namespace, kind, job_counts)
+            .await
+            .in_scope(&scope)?,
+        WorkloadKind::CronJob => list_infos(&client, &namespace, kind, cron_job_counts)
+            .await
+            .in_scope(&scope)?,
+    };
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_workload_details(
+    state: State<'_, AppState>,
+    context: String,
+    namespace: String,
+    kind: WorkloadKind,
+    name: String,
+) -> Result<WorkloadDetails, K8sError> {
+    let client = state.clients.get(&context).await?;
+    let scope = ErrorScope::namespaced(&context, &namespace).resource(kind.resource(), &name);
+
+    workload_details(client, &namespace, kind, &name)
+        .await
+        .in_scope(&scope)
+}
+
+async fn workload_details(
+    client: Client,
+    namespace: &str,
+    kind: WorkloadKind,
+    name: &str,
+) -> Result<WorkloadDetails, K8sError> {
+    match kind {
+        WorkloadKind::Deployment => {
+            let d: Deployment = Api::namespaced(client, namespace).get(name).await?;
+            let status = d.status.as_ref();
+            build_details(
+                kind,
+                &d.metadata,
+                deployment_counts(&d),
+                d.spec.as_ref().map(|s| &s.selector),
+                conditions!(status.and_then(|s| s.conditions.as_ref()), last_update_time),
+            )
+        }
+        WorkloadKind::StatefulSet => {
+            let s: StatefulSet = Api::namespaced(client, namespace).get(name).await?;
+            build_details(
+                kind,
+                &s.metadata,
+                stateful_set_counts(&s),
+                s.spec.as_ref().map(|s| &s.selector),
+                conditions!(s.status.as_ref().and_then(|s| s.conditions.as_ref())),
+            )
+        }
+        WorkloadKind::DaemonSet => {
+            let d: DaemonSet = Api::namespaced(client, namespace).get(name).await?;
+            build_details(
+                kind,
+                &d.metadata,
+                daemon_set_counts(&d),
+                d.spec.as_ref().map(|s| &s.selector),
+                conditions!(d.status.as_ref().and_then(|s| s.conditions.as_ref())),
+            )
+        }
+        WorkloadKind::ReplicaSet => {
+            let r: ReplicaSet = Api::namespaced(client, namespace).get(name).await?;
+            build_details(
+                kind,
+                &r.metadata,
+                replica_set_counts(&r),
+                r.spec.as_ref().map(|s| &s.selector),
+                conditions!(r.status.as_ref().and_then(|s| s.conditions.as_ref())),
+            )
+        }
+        WorkloadKind::Job => {
+            let j: Job = Api::namespaced(client, namespace).get(name).await?;
+            let mut details = build_details(
+                kind,
+                &j.metadata,
+                job_counts(&j),
+                j.spec.as_ref().and_then(|s| s.selector.as_ref()),
+                conditions!(
+                    j.status.as_ref().and_then(|s| s.conditions.as_ref()),
+                    last_probe_time
+                ),
+            )?;
+            details.suspended = Some(j.spec.as_ref().and_then(|s| s.suspend).unwrap_or(false));
+            Ok(details)
+        }
+        WorkloadKind::CronJob => {
+            let c: CronJob = Api::namespaced(client, namespace).get(name).await?;
+            let spec = c.spec.as_ref();
+            let mut details = build_details(
+                kind,
+                &c.metadata,
+                cron_job_counts(&c),
+                // Pods are selected through the CronJob's Jobs, not a selector of its own
+                None,
+                Vec::new(),
+            )?;
+            details.schedule = spec.map(|s| s.schedule.clone());
+            details.suspended = Some(spec.and_then(|s| s.suspend).unwrap_or(false));
+            Ok(details)
+        }
+    }
+}
+
+/// Resolve the label selectors that select a workload's pods. A CronJob has
+/// one selector per Job it currently owns.
+pub(crate) async fn resolve_selectors(
+    client: &Client,
+    namespace: &str,
+    kind: WorkloadKind,
+    name: &str,
+) -> Result<Vec<LabelSelector>, K8sError> {
+    let selectors = match kind {
+        WorkloadKind::Deployment => {
+            let d: Deployment = Api::namespaced(client.clone(), namespace).get(name).await?;
+            d.spec.map(|s| s.selector).into_iter().collect()
+        }
+        WorkloadKind::StatefulSet => {
+            let s: StatefulSet = Api::namespaced(client.clone(), namespace).get(name).await?;
+            s.spec.map(|s| s.selector).into_iter().collect()
+        }
+        WorkloadKind::DaemonSet => {
+            let d: DaemonSet = Api::namespaced(client.clone(), namespace).get(name).await?;
+            d.spec.map(|s| s.selector).into_iter().collect()
+        }
+        WorkloadKind::ReplicaSet => {
+            let r: ReplicaSet = Api::namespaced(client.clone(), namespace).get(name).await?;
+            r.spec.map(|s| s.selector).into_iter().collect()
+        }
+        WorkloadKind::Job => {
+            let j: Job = Api::namespaced(client.clone(), namespace).get(name).await?;
+            j.spec.and_then(|s| s.selector).into_iter().collect()
+        }
+        WorkloadKind::CronJob => {
+            let cron_job: CronJob = Api::namespaced(client.clone(), namespace).get(name).await?;
+            owned_jobs(client, namespace, &cron_job)
+                .await?
+                .into_iter()
+                .filter_map(|job| job.spec.and_then(|s| s.selector))
+                .collect()
+        }
+    };
+
+    Ok(selectors)
+}
+
+/// The Jobs a CronJob owns. Jobs get the labels of the CronJob's job template,
+/// so those narrow the list down instead of listing every Job of the
+/// namespace. Without template labels, only the Jobs still running can be
+/// found, from the CronJob's status.
+async fn owned_jobs(
+    client: &Client,
+    namespace: &str,
+    cron_job: &CronJob,
+) -> Result<Vec<Job>, K8sError> {
+    let jobs: Api<Job> = Api::namespaced(client.clone(), namespace);
+    let template_labels = cron_job
+        .spec
+        .as_ref()
+        .and_then(|s| s.job_template.metadata.as_ref())
+        .and_then(|m| m.labels.clone())
+        .filter(|labels| !labels.is_empty());
+
+    let Some(labels) = template_labels else {
+        let mut active = Vec::new();
+        let refs = cron_job
+            .status
+            .iter()
+            .flat_map(|s| s.active.iter().flatten());
+        for name in refs.filter_map(|r| r.name.as_deref()) {
+            // A Job that finished and got cleaned up since the status was written
+            active.extend(jobs.get_opt(name).await?);
+        }
+        return Ok(active);
+    };
+
+    let selector = to_selector_string(&LabelSelector {
+        match_labels: Some(labels),
+        ..Default::default()
+    })?;
+    let uid = cron_job.metadata.uid.as_deref().unwrap_or_default();
+    Ok(jobs
+        .list(&ListParams::default().labels(&selector))
+        .await?
+        .items
+        .into_iter()
+        .filter(|job| {
+            job.metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .any(|owner| owner.kind == "CronJob" && owner.uid == uid)
+        })
+        .collect())
+}
+
+/// List the pods belonging to a workload
+pub(crate) async fn list_workload_pods(
+    client: &Client,
+    namespace: &str,
+    kind: WorkloadKind,
+    name: &str,
+) -> Result<Vec<Pod>, K8sError> {
+    let selectors = resolve_selectors(client, namespace, kind, name).await?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+
+    for selector in selectors {
+        let selector = to_selector_string(&selector)?;
+
+        // An empty selector would match every pod in the namespace
+        if selector.is_empty() {
+            continue;
+        }
+
+        for pod in pods
+            .list(&ListParams::default().labels(&selector))
+            .await?
+            .items
+        {
+            if seen.insert(pod.metadata.name.clone()) {
+                result.push(pod);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+async fn list_infos<K>(
+    client: &Client,
+    namespace: &str,
+    kind: WorkloadKind,
+    counts: fn(&K) -> (i32, i32, i32),
+) -> Result<Vec<WorkloadInfo>, K8sError>
+where
+    K: Resource<Scope = k8s_openapi::NamespaceResourceScope>
+        + Clone
+        + std::fmt::Debug
+        + serde::de::DeserializeOwned,
+    K::DynamicType: Default,
+{
+    let api: Api<K> = Api::namespaced(client.clone(), namespace);
+    let list = api.list(&ListParams::default()).await?;
+
+    Ok(list
+        .items
+        .iter()
+        .map(|item| {
+            let (replicas, available_replicas, ready_replicas) = counts(item);
+            let meta = item.meta();
+
+            WorkloadInfo {
+                kind,
+                name: meta.name.clone().unwrap_or_default(),
+                namespace: meta.namespace.clone().unwrap_or_default(),
+                replicas,
+                available_replicas,
+                ready_replicas,
+            }
+        })
+        .collect())
+}
+
+fn build_details(
+    kind: WorkloadKind,
+    metadata: &ObjectMeta,
+    (replicas, available_replicas, ready_replicas): (i32, i32, i32),
+    selector: Option<&LabelSelector>,
+    conditions: Vec<DeploymentCondition>,
+) -> Result<WorkloadDetails, K8sError> {
+    Ok(WorkloadDetails {
+        kind,
+        name: metadata.name.clone().unwrap_or_default(),
+        namespace: metadata.namespace.clone().unwrap_or_default(),
+        replicas,
+        available_replicas,
+        ready_replicas,
+        creation_timestamp: metadata
+            .creation_timestamp
+            .as_ref()
+            .map(|t| t.0.to_rfc3339()),
+        labels: metadata.labels.clone().unwrap_or_default(),
+        annotations: metadata.annotations.clone().unwrap_or_default(),
+        selector: selector
+            .and_then(|s| s.match_labels.clone())
+            .unwrap_or_default(),
+        selector_string: selector.map(to_selector_string).transpose()?,
+        conditions,
+        schedule: None,
+        suspended: None,
+    })
+}
+
+// Replica counts per kind as (replicas, available, ready)
+
+fn deployment_counts(d: &Deployment) -> (i32, i32, i32) {
+    let status = d.status.as_ref();
+    (
+        d.spec.as_ref().and_then(|s| s.replicas).unwrap_or(0),
+        status.and_then(|s| s.available_replicas).unwrap_or(0),
+        status.and_then(|s| s.ready_replicas).unwrap_or(0),
+    )
+}
+
+fn stateful_set_counts(s: &StatefulSet) -> (i32, i32, i32) {
+    let status = s.status.as_ref();
+    (
+        s.spec.as_ref().and_then(|s| s.replicas).unwrap_or(0),
+        status.and_then(|s| s.available_replicas).unwrap_or(0),
+        status.and_then(|s| s.ready_replicas).unwrap_or(0),
+    )
+}
+
+fn daemon_set_counts(d: &DaemonSet) -> (i32, i32, i32) {
+    d.status
+        .as_ref()
+        .map(|s| {
+            (
+                s.desired_number_scheduled,
+                s.number_available.unwrap_or(0),
+                s.number_ready,
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn replica_set_counts(r: &ReplicaSet) -> (i32, i32, i32) {
+    let status = r.status.as_ref();
+    (
+        r.spec.as_ref().and_then(|s| s.replicas).unwrap_or(0),
+        status.and_then(|s| s.available_replicas).unwrap_or(0),
+        status.and_then(|s| s.ready_replicas).unwrap_or(0),
+    )
+}
+
+fn job_counts(j: &Job) -> (i32, i32, i32) {
+    let status = j.status.as_ref();
+    (
+        j.spec.as_ref().and_then(|s| s.completions).unwrap_or(1),
+        status.and_then(|s| s.succeeded).unwrap_or(0),
+        status.and_then(|s| s.ready).unwrap_or(0),
+    )
+}
+
+fn cron_job_counts(c: &CronJob) -> (i32, i32, i32) {
+    let active = c
+        .status
+        .as_ref()
+        .and_then(|s| s.active.as_ref())
+        .map(|a| a.len() as i32)
+        .unwrap_or(0);
+    (active, 0, 0)
+}
@@ -1,8 +1,20 @@
+mod ansi;
 mod error;
+mod formats;
 mod k8s;
+mod logfmt;
 mod models;
+mod parser;
+mod query;
+mod settings;
+mod state;
+mod timestamp;
 
-use k8s::{clusters, deployments, logs, namespaces, pods};
+use k8s::{
+    clusters, compare, config_watcher, deployments, events, logs, metrics, namespaces, pods,
+    streams, workloads,
+};
+use state::AppState;
 use tauri::{
     image::Image,
     menu::{AboutMetadata, Menu, PredefinedMenuItem, Submenu},
@@ -13,6 +25,7 @@ use tauri::{
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .manage(AppState::default())
         .setup(|app| {
             // Get version from tauri.conf.json
             let version = app.package_info().version.to_string();
@@ -87,6 +100,8 @@ pub fn run() {
             let menu = Menu::with_items(app, &[&app_submenu, &edit_submenu, &window_submenu])?;
             app.set_menu(menu)?;
 
+            config_watcher::spawn_kubeconfig_watcher(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -94,10 +109,33 @@ pub fn run() {
             namespaces::get_namespaces,
             deployments::get_deployments,
             deployments::get_deployment_details,
+            workloads::get_workloads,
+            workloads::get_workload_details,
             pods::get_pods,
+            pods::get_workload_pods,
+            pods::get_pods_by_selector,
             pods::get_pod_details,
             logs::get_pod_logs,
+            logs::poll_pod_logs,
             logs::search_deployment_logs,
+            logs::search_workload_logs,
+            logs::search_revision_logs,
+            logs::search_selector_logs,
+            logs::cancel_search,
+            logs::validate_log_query,
+            compare::compare_logs,
+            streams::start_log_stream,
+            streams::start_deployment_log_stream,
+            streams::start_selector_log_stream,
+            streams::stop_log_stream,
+            events::get_events,
+            events::start_event_stream,
+            events::stop_event_stream,
+            metrics::get_pod_metrics,
+            metrics::get_deployment_metrics,
+            metrics::get_workload_metrics,
+            settings::get_settings,
+            settings::update_settings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
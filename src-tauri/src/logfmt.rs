@@ -0,0 +1,119 @@
+//! Parser for logfmt lines such as `level=info msg="request done" user=42`.
+
+/// Split a logfmt line into its key/value pairs, in order.
+///
+/// Returns `None` unless the whole line is made of `key=value` pairs, so plain
+/// text that merely contains a `key=value` (e.g. `Listening on port=8080`) is
+/// left alone. Values may be double-quoted with `\"`, `\\`, `\n` and `\t`
+/// escapes.
+pub fn parse(line: &str) -> Option<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            if !is_key_char(c) {
+                return None;
+            }
+            key.push(c);
+            chars.next();
+        }
+
+        if key.is_empty() || chars.next() != Some('=') {
+            return None;
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        other => value.push(other),
+                    },
+                    c => value.push(c),
+                }
+            }
+            // A closing quote must end the value
+            if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                return None;
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+
+        pairs.push((key, value));
+
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    }
+
+    // A lone `a=b` is too weak a signal unless it's a well-known key
+    let convincing = pairs.len() >= 2
+        || pairs
+            .first()
+            .is_some_and(|(key, _)| matches!(key.as_str(), "level" | "msg" | "message"));
+
+    convincing.then_some(pairs)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@' | ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(expected: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+        Some(
+            expected
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn splits_bare_and_quoted_values() {
+        assert_eq!(
+            parse(r#"level=info msg="request done" user=42"#),
+            pairs(&[("level", "info"), ("msg", "request done"), ("user", "42")])
+        );
+    }
+
+    #[test]
+    fn unescapes_quoted_values() {
+        assert_eq!(
+            parse(r#"msg="say \"hi\"\n\tback\\slash" empty="""#),
+            pairs(&[("msg", "say \"hi\"\n\tback\\slash"), ("empty", "")])
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_or_trailing_quotes() {
+        assert_eq!(parse(r#"level=info msg="never closed"#), None);
+        assert_eq!(parse(r#"level=info msg="done"x"#), None);
+    }
+
+    #[test]
+    fn leaves_plain_text_alone() {
+        assert_eq!(parse("Listening on port=8080"), None);
+        assert_eq!(parse("port=8080"), None);
+        assert_eq!(parse("level=warn").map(|p| p.len()), Some(1));
+    }
+}
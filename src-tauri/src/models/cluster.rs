@@ -5,6 +5,8 @@ pub struct ClusterInfo {
     pub name: String,
     pub server: String,
     pub is_current: bool,
+    /// Kubeconfig file the context was loaded from
+    pub source_file: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,8 +38,13 @@ pub struct DeploymentDetails {
     pub creation_timestamp: Option<String>,
     pub labels: std::collections::BTreeMap<String, String>,
     pub annotations: std::collections::BTreeMap<String, String>,
+    /// `matchLabels` of the selector only
     pub selector: std::collections::BTreeMap<String, String>,
+    /// The full selector, `matchExpressions` included, in label selector syntax
+    pub selector_string: String,
     pub conditions: Vec<DeploymentCondition>,
+    /// ReplicaSets owned by the deployment, newest revision first
+    pub revisions: Vec<DeploymentRevision>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,3 +56,21 @@ pub struct DeploymentCondition {
     pub last_update_time: Option<String>,
     pub last_transition_time: Option<String>,
 }
+
+/// A rollout revision of a deployment, i.e. one of its ReplicaSets
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeploymentRevision {
+    /// `deployment.kubernetes.io/revision`, 0 when the annotation is missing
+    pub revision: i64,
+    pub replica_set: String,
+    /// Container images of the pod template
+    pub images: Vec<String>,
+    pub creation_timestamp: Option<String>,
+    /// `kubernetes.io/change-cause`, as set by `kubectl annotate` or `--record`
+    pub change_cause: Option<String>,
+    pub replicas: i32,
+    pub ready_replicas: i32,
+    pub available_replicas: i32,
+    /// Whether this is the revision the deployment currently rolls out
+    pub current: bool,
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{LogSearchFailure, WorkloadKind};
+
+/// Logs to compare: whose, and over which time range
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSelection {
+    pub target: CompareTarget,
+    pub since_seconds: Option<i64>,
+    /// Absolute range (RFC3339), as in `LogSearchOptions`
+    pub since_time: Option<String>,
+    pub until_time: Option<String>,
+}
+
+/// Pods whose logs make up one side of a comparison
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompareTarget {
+    /// One pod, or a single container of it
+    Pod {
+        name: String,
+        container: Option<String>,
+    },
+    /// The pods of one rollout revision of a deployment
+    Revision { deployment: String, revision: i64 },
+    Workload {
+        workload: WorkloadKind,
+        name: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CompareOptions {
+    /// Occurrences a template needs on the side where it shows up to be
+    /// reported as new or vanished (default 3)
+    pub min_count: Option<u64>,
+    /// Factor by which a template's rate must grow to be reported as
+    /// increased (default 2.0)
+    pub min_ratio: Option<f64>,
+    /// Maximum number of changes returned (default 100)
+    pub limit: Option<usize>,
+    /// Lines fetched per container (default 5000)
+    pub tail_lines: Option<i64>,
+}
+
+/// Result of comparing a baseline selection of logs with a candidate one
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogComparison {
+    pub baseline: LogProfile,
+    pub candidate: LogProfile,
+    /// Templates that are new, vanished or increased in the candidate, new
+    /// ones first, then increased, then vanished
+    pub changes: Vec<TemplateChange>,
+}
+
+/// Summary of one side of a comparison
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogProfile {
+    pub pods: Vec<String>,
+    pub total_entries: u64,
+    /// Entries per normalized level; entries without a level count as `NONE`
+    pub level_counts: BTreeMap<String, u64>,
+    /// Number of distinct message templates
+    pub template_count: usize,
+    /// Containers whose logs couldn't be fetched
+    pub failures: Vec<LogSearchFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateChangeKind {
+    New,
+    Increased,
+    Vanished,
+}
+
+/// A message template whose frequency differs between the two sides
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateChange {
+    pub change: TemplateChangeKind,
+    /// The message with variable parts replaced by placeholders such as
+    /// `<num>`, `<uuid>` or `<ip>`
+    pub template: String,
+    /// One original message matching the template
+    pub example: String,
+    pub level: Option<String>,
+    pub baseline_count: u64,
+    pub candidate_count: u64,
+    /// Occurrences per 1000 entries of each side
+    pub baseline_rate: f64,
+    pub candidate_rate: f64,
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::SerializedError;
+
+/// Objects whose events to list or watch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventTarget {
+    Pod {
+        name: String,
+    },
+    /// The Deployment itself, its ReplicaSets and their pods
+    Deployment {
+        name: String,
+    },
+    /// Every object of the namespace
+    Namespace,
+}
+
+/// A core/v1 `Event`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KubeEvent {
+    /// `Normal` or `Warning`
+    pub event_type: String,
+    /// e.g. `OOMKilling`, `FailedScheduling`, `Unhealthy`, `BackOff`
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    /// Kind and name of the object the event is about
+    pub object_kind: Option<String>,
+    pub object_name: Option<String>,
+    /// Number of occurrences folded into this event
+    pub count: Option<i32>,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// Last occurrence in UTC, in the same format as `LogEntry::timestamp_utc`
+    pub timestamp_utc: Option<String>,
+    /// Component that reported the event, e.g. `kubelet`
+    pub component: Option<String>,
+}
+
+/// Messages pushed to the webview over an event stream channel
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum EventStreamEvent {
+    /// Events that existed when the stream started, oldest first
+    Initial(Vec<KubeEvent>),
+    /// A new event, or a new occurrence of an existing one
+    Event(KubeEvent),
+    Error(SerializedError),
+    Ended,
+}
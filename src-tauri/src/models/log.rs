@@ -1,20 +1,200 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::ContainerKind;
+use crate::error::SerializedError;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LogEntry {
+    /// Timestamp as written in the line, or the kubelet's when it has none
     pub timestamp: Option<String>,
+    /// `timestamp` in UTC as RFC 3339 with nanoseconds, e.g.
+    /// `2024-10-26T12:00:00.123456789Z`. Fixed width, so it sorts as a string.
+    pub timestamp_utc: Option<String>,
     pub level: Option<String>,
     pub message: String,
     pub raw: String,
     pub is_json: bool,
+    /// For events, the object the event is about
     pub pod_name: String,
     pub container_name: String,
+    pub source: LogSource,
+    /// Structured fields: every key of a JSON line flattened to dot paths, or
+    /// the keys of a logfmt line besides level, message and time
+    pub fields: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub well_known: WellKnownFields,
+    /// Original coloring of `message`, when the line had ANSI escape codes.
+    /// `message` and `raw` have the codes stripped.
+    pub spans: Option<Vec<StyledSpan>>,
+}
+
+/// Where an entry comes from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    /// A line logged by a container
+    Container,
+    /// A Kubernetes event interleaved with the logs
+    Event,
+}
+
+/// Run of text with one style. Colors are basic names (`red`, `bright_blue`)
+/// or `#rrggbb`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StyledSpan {
+    pub text: String,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+/// Fields with a common meaning across logging libraries, taken from `fields`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WellKnownFields {
+    pub logger: Option<String>,
+    /// Call site, usually `file:line`
+    pub caller: Option<String>,
+    pub trace_id: Option<String>,
+    pub span_id: Option<String>,
+    pub error: Option<String>,
+    pub stack: Option<String>,
+}
+
+#[cfg(test)]
+impl LogEntry {
+    /// Plain container entry with just a message, for tests
+    pub(crate) fn from_message(message: &str) -> Self {
+        LogEntry {
+            timestamp: None,
+            timestamp_utc: None,
+            level: None,
+            message: message.to_string(),
+            raw: message.to_string(),
+            is_json: false,
+            pod_name: "api-7d9f8-x4k2p".to_string(),
+            container_name: "app".to_string(),
+            source: LogSource::Container,
+            fields: BTreeMap::new(),
+            well_known: WellKnownFields::default(),
+            spans: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LogSearchResult {
     pub pod_name: String,
     pub container_name: String,
+    pub container_kind: ContainerKind,
     pub total_matches: i32,
     pub entries: Vec<LogEntry>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PodLogOptions {
+    /// Defaults to the pod's first regular container
+    pub container: Option<String>,
+    pub since_seconds: Option<i64>,
+    /// RFC 3339 start of an absolute time range; can't be combined with `since_seconds`
+    pub since_time: Option<String>,
+    /// RFC 3339 end of an absolute time range
+    pub until_time: Option<String>,
+    /// Last lines of the range (of the whole log when there's none)
+    pub tail_lines: Option<i64>,
+    /// Fetch the previous (e.g. crashed) instance of the container
+    pub previous: Option<bool>,
+    pub multiline: Option<MultilineOptions>,
+    /// Interleave the pod's events within the range, as entries with the
+    /// `event` source. Not supported by `poll_pod_logs`.
+    pub include_events: Option<bool>,
+}
+
+/// Lines returned by `poll_pod_logs`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Opaque position to pass to the next call; `None` until a line was seen
+    pub cursor: Option<String>,
+}
+
+/// How continuation lines such as stack trace frames are folded into the
+/// preceding entry
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MultilineOptions {
+    /// Grouping is on unless this is `false`
+    pub enabled: Option<bool>,
+    /// Extra regexes marking a line (without its timestamp) as a continuation,
+    /// on top of the built-in rules
+    #[serde(default)]
+    pub continuation_patterns: Vec<String>,
+    /// Maximum number of lines in one entry
+    pub max_lines: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSearchFailure {
+    pub pod_name: String,
+    pub container_name: String,
+    pub error: SerializedError,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogSearchResponse {
+    pub results: Vec<LogSearchResult>,
+    pub failures: Vec<LogSearchFailure>,
+}
+
+/// Payload of the `log-search-progress` event, sent once per searched container
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSearchProgress {
+    pub search_id: Option<String>,
+    pub completed: usize,
+    pub total: usize,
+    pub result: Option<LogSearchResult>,
+    pub failure: Option<LogSearchFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogSearchOptions {
+    pub keyword: Option<String>,
+    pub log_level: Option<String>,
+    pub since_seconds: Option<i64>,
+    /// RFC 3339 start of an absolute time range; can't be combined with `since_seconds`
+    pub since_time: Option<String>,
+    /// RFC 3339 end of an absolute time range
+    pub until_time: Option<String>,
+    /// Boolean query, see `crate::query` for the syntax
+    pub query: Option<String>,
+    /// Search the previous (e.g. crashed) instance of each container
+    pub previous: Option<bool>,
+    /// Id that `cancel_search` can use to abort the search
+    pub search_id: Option<String>,
+    /// Number of containers fetched concurrently
+    pub parallelism: Option<usize>,
+    pub multiline: Option<MultilineOptions>,
+}
+
+/// Messages pushed to the webview over a log stream channel
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum LogStreamEvent {
+    Entries(Vec<LogEntry>),
+    PodAttached(String),
+    PodDetached(String),
+    Error(SerializedError),
+    Ended,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogStreamOptions {
+    pub container: Option<String>,
+    pub tail_lines: Option<i64>,
+    pub multiline: Option<MultilineOptions>,
+    /// Interleave the events of the pod or deployment, as entries with the
+    /// `event` source. Selector streams have no events.
+    pub include_events: Option<bool>,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Resource usage of a pod as reported by metrics-server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PodMetrics {
+    pub pod_name: String,
+    /// When the usage was sampled; `None` when no sample exists yet, e.g. for
+    /// a pod that just started
+    pub timestamp: Option<String>,
+    /// Length of the sampling window, e.g. `15s`
+    pub window: Option<String>,
+    pub containers: Vec<ContainerMetrics>,
+}
+
+/// Usage of one container next to its requests and limits
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContainerMetrics {
+    pub name: String,
+    pub cpu_millicores: Option<f64>,
+    pub memory_bytes: Option<f64>,
+    pub cpu_request_millicores: Option<f64>,
+    pub cpu_limit_millicores: Option<f64>,
+    pub memory_request_bytes: Option<f64>,
+    pub memory_limit_bytes: Option<f64>,
+    /// Usage as a percentage of the limit, when the container has one
+    pub cpu_percent_of_limit: Option<f64>,
+    pub memory_percent_of_limit: Option<f64>,
+}
@@ -1,7 +1,17 @@
 mod cluster;
+mod compare;
+mod event;
 mod log;
+mod metrics;
 mod pod;
+mod settings;
+mod workload;
 
 pub use cluster::*;
+pub use compare::*;
+pub use event::*;
 pub use log::*;
+pub use metrics::*;
 pub use pod::*;
+pub use settings::*;
+pub use workload::*;
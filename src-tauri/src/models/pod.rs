@@ -12,6 +12,26 @@ pub struct PodInfo {
     pub ip: String,
     pub node: String,
     pub containers: Vec<String>,
+    /// All containers that can be used as a log source, including init,
+    /// sidecar and ephemeral containers
+    pub container_sources: Vec<ContainerSource>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerKind {
+    Init,
+    /// Init container with restartPolicy Always
+    Sidecar,
+    Regular,
+    /// Debug container added with `kubectl debug`
+    Ephemeral,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContainerSource {
+    pub name: String,
+    pub kind: ContainerKind,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,15 +51,28 @@ pub struct PodDetails {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ContainerDetails {
     pub name: String,
+    pub kind: ContainerKind,
     pub image: String,
     pub ready: bool,
     pub restart_count: i32,
     pub state: String,
+    pub last_state: Option<ContainerTermination>,
     pub env_vars: Vec<EnvVar>,
     pub ports: Vec<ContainerPort>,
     pub resources: ResourceRequirements,
 }
 
+/// How the previous instance of a container terminated
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContainerTermination {
+    pub exit_code: i32,
+    pub reason: Option<String>,
+    pub signal: Option<i32>,
+    pub message: Option<String>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EnvVar {
     pub name: String,
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// User preferences persisted in `settings.json` under the app config directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Kubeconfig files or directories loaded after those from `KUBECONFIG`
+    pub extra_kubeconfig_paths: Vec<String>,
+    /// Custom line formats, applied to the containers picked by `parser_assignments`
+    pub parser_rules: Vec<ParserRule>,
+    /// Extra level spellings and what they mean, e.g. `"SEVERE": "ERROR"`
+    pub level_aliases: BTreeMap<String, String>,
+    /// Which containers use which parser rule; the first match wins
+    pub parser_assignments: Vec<ParserAssignment>,
+}
+
+/// A named line format
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParserRule {
+    pub name: String,
+    /// Regex whose named groups `timestamp`, `level` and `message` fill those
+    /// fields of the entry; any other named group becomes a structured field
+    pub pattern: String,
+}
+
+/// Assign a parser rule to containers. Every criterion given must match, and
+/// each may use `*` and `?` wildcards.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParserAssignment {
+    /// Name of a `ParserRule`, of a built-in format (`klog`, `nginx`/`apache`,
+    /// `envoy`, `postgres`, `redis`), or `plain` to turn format detection off
+    pub parser: String,
+    pub namespace: Option<String>,
+    /// Name of the owning Deployment, StatefulSet, DaemonSet, Job...
+    pub workload: Option<String>,
+    pub container: Option<String>,
+    pub image: Option<String>,
+}
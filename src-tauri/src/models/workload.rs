@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::DeploymentCondition;
+
+/// Kinds of workload whose pods can be browsed and searched
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadKind {
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+    ReplicaSet,
+    Job,
+    CronJob,
+}
+
+impl WorkloadKind {
+    /// Plural API resource name, as used by kubectl
+    pub fn resource(&self) -> &'static str {
+        match self {
+            WorkloadKind::Deployment => "deployments",
+            WorkloadKind::StatefulSet => "statefulsets",
+            WorkloadKind::DaemonSet => "daemonsets",
+            WorkloadKind::ReplicaSet => "replicasets",
+            WorkloadKind::Job => "jobs",
+            WorkloadKind::CronJob => "cronjobs",
+        }
+    }
+}
+
+/// Summary of a workload. Replica counts are interpreted per kind: for a
+/// DaemonSet they count scheduled nodes, for a Job `replicas` is the number of
+/// completions and `available_replicas` the number of succeeded pods, and for a
+/// CronJob `replicas` is the number of currently active Jobs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkloadInfo {
+    pub kind: WorkloadKind,
+    pub name: String,
+    pub namespace: String,
+    pub replicas: i32,
+    pub available_replicas: i32,
+    pub ready_replicas: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkloadDetails {
+    pub kind: WorkloadKind,
+    pub name: String,
+    pub namespace: String,
+    pub replicas: i32,
+    pub available_replicas: i32,
+    pub ready_replicas: i32,
+    pub creation_timestamp: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+    /// `matchLabels` of the selector only
+    pub selector: BTreeMap<String, String>,
+    /// The full selector, `matchExpressions` included, in label selector
+    /// syntax (e.g. `app=api,tier in (web,worker)`); `None` for CronJobs
+    pub selector_string: Option<String>,
+    pub conditions: Vec<DeploymentCondition>,
+    /// Cron schedule, CronJobs only
+    pub schedule: Option<String>,
+    /// Whether scheduling is suspended, Jobs and CronJobs only
+    pub suspended: Option<bool>,
+}
@@ -0,0 +1,390 @@
+//! Per-container parsing configuration: user-defined line formats and level
+//! aliases from the app settings, and the multiline options of a request.
+
+use k8s_openapi::api::core::v1::Pod;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::error::K8sError;
+use crate::formats::{self, Format, Parsed};
+use crate::k8s::logs::{normalize_log_level, well_known_fields, MultilineRules};
+use crate::k8s::pods::workload_name;
+use crate::models::{AppSettings, LogEntry, LogSource, MultilineOptions, ParserAssignment};
+use crate::query::glob_match;
+use crate::settings::SettingsCache;
+
+/// Compiled parser settings for one fetch, search or stream, from which each
+/// container gets its `LineParser`
+#[derive(Clone)]
+pub(crate) struct ParseConfig {
+    rules: Arc<HashMap<String, Regex>>,
+    level_aliases: Arc<HashMap<String, String>>,
+    assignments: Arc<Vec<ParserAssignment>>,
+    multiline: MultilineRules,
+}
+
+impl ParseConfig {
+    /// Parser rules of the saved settings, compiled once per settings change,
+    /// with the caller's multiline options
+    pub(crate) fn load(
+        settings: &SettingsCache,
+        multiline: Option<&MultilineOptions>,
+    ) -> Result<Self, K8sError> {
+        let mut config = settings.parse_config()?;
+        config.multiline = MultilineRules::compile(multiline)?;
+        Ok(config)
+    }
+
+    pub(crate) fn from_settings(
+        settings: &AppSettings,
+        multiline: Option<&MultilineOptions>,
+    ) -> Result<Self, K8sError> {
+        let rules = settings
+            .parser_rules
+            .iter()
+            .map(|rule| Ok((rule.name.clone(), Regex::new(&rule.pattern)?)))
+            .collect::<Result<HashMap<_, _>, K8sError>>()?;
+
+        if let Some(assignment) = settings.parser_assignments.iter().find(|a| {
+            !rules.contains_key(&a.parser)
+                && Format::from_name(&a.parser).is_none()
+                && a.parser != PLAIN
+        }) {
+            return Err(K8sError::Settings(format!(
+                "Parser assignment refers to unknown parser '{}'",
+                assignment.parser
+            )));
+        }
+
+        let level_aliases = settings
+            .level_aliases
+            .iter()
+            .map(|(alias, level)| (alias.to_uppercase(), normalize_log_level(level)))
+            .collect();
+
+        Ok(Self {
+            rules: Arc::new(rules),
+            level_aliases: Arc::new(level_aliases),
+            assignments: Arc::new(settings.parser_assignments.clone()),
+            multiline: MultilineRules::compile(multiline)?,
+        })
+    }
+
+    /// Build the parser for a container of a pod, picking the first assignment
+    /// that matches it
+    pub(crate) fn parser_for(&self, pod: &Pod, container_name: &str) -> LineParser {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+        let workload = workload_name(pod);
+        let image = pod
+            .spec
+            .as_ref()
+            .and_then(|spec| {
+                spec.containers
+                    .iter()
+                    .chain(spec.init_containers.iter().flatten())
+                    .map(|c| (&c.name, &c.image))
+                    .chain(
+                        spec.ephemeral_containers
+                            .iter()
+                            .flatten()
+                            .map(|c| (&c.name, &c.image)),
+                    )
+                    .find(|(name, _)| *name == container_name)
+            })
+            .and_then(|(_, image)| image.as_deref());
+
+        let matches = |pattern: &Option<String>, value: Option<&str>| match pattern {
+            Some(pattern) => value.is_some_and(|v| glob_match(pattern, v)),
+            None => true,
+        };
+
+        // User rules shadow the built-in formats of the same name
+        let assigned = self
+            .assignments
+            .iter()
+            .find(|a| {
+                matches(&a.namespace, Some(namespace))
+                    && matches(&a.workload, workload.as_deref())
+                    && matches(&a.container, Some(container_name))
+                    && matches(&a.image, image)
+            })
+            .and_then(|a| match self.rules.get(&a.parser) {
+                Some(rule) => Some(Assigned::Rule(rule.clone())),
+                None if a.parser == PLAIN => Some(Assigned::Plain),
+                None => Format::from_name(&a.parser).map(Assigned::Format),
+            });
+
+        LineParser {
+            pod_name: pod.metadata.name.clone().unwrap_or_default(),
+            container_name: container_name.to_string(),
+            assigned,
+            level_aliases: self.level_aliases.clone(),
+            multiline: self.multiline.clone(),
+        }
+    }
+}
+
+/// Assignment name that turns format detection off for a container
+const PLAIN: &str = "plain";
+
+/// Parsing forced on a container by a parser assignment
+#[derive(Clone)]
+enum Assigned {
+    /// User-defined format
+    Rule(Regex),
+    /// Built-in format, used instead of auto-detection
+    Format(Format),
+    /// No structure at all, every line is plain text
+    Plain,
+}
+
+/// How the lines of one container are turned into entries
+#[derive(Clone)]
+pub(crate) struct LineParser {
+    pub(crate) pod_name: String,
+    pub(crate) container_name: String,
+    assigned: Option<Assigned>,
+    level_aliases: Arc<HashMap<String, String>>,
+    pub(crate) multiline: MultilineRules,
+}
+
+#[cfg(test)]
+impl LineParser {
+    /// Parser with no rule or level aliases, for tests
+    pub(crate) fn plain() -> Self {
+        LineParser {
+            pod_name: "api-7d9f8-x4k2p".to_string(),
+            container_name: "app".to_string(),
+            assigned: None,
+            level_aliases: Arc::default(),
+            multiline: MultilineRules::compile(None).expect("default rules compile"),
+        }
+    }
+}
+
+impl LineParser {
+    /// Normalize a level, honoring the user's aliases
+    pub(crate) fn normalize_level(&self, level: &str) -> String {
+        self.level_aliases
+            .get(&level.to_uppercase())
+            .cloned()
+            .unwrap_or_else(|| normalize_log_level(level))
+    }
+
+    /// Whether the container is assigned plain text, skipping format detection
+    pub(crate) fn plain_only(&self) -> bool {
+        matches!(self.assigned, Some(Assigned::Plain))
+    }
+
+    /// Parse a line (without its kubectl timestamp) with the assigned built-in
+    /// format, or with the first one that matches when none is assigned
+    pub(crate) fn parse_format(&self, body: &str) -> Option<Parsed> {
+        match &self.assigned {
+            Some(Assigned::Format(format)) => format.parse(body),
+            Some(Assigned::Plain) => None,
+            _ => formats::detect(body),
+        }
+    }
+
+    /// Parse a line (without its kubectl timestamp) with the assigned rule.
+    /// Named groups `timestamp`, `level` and `message` fill those fields, any
+    /// other named group ends up in `fields`. `None` if there's no rule or the
+    /// line doesn't match it.
+    pub(crate) fn apply_rule(
+        &self,
+        body: &str,
+        fallback_timestamp: Option<String>,
+        raw: &str,
+    ) -> Option<LogEntry> {
+        let Some(Assigned::Rule(rule)) = &self.assigned else {
+            return None;
+        };
+        let captures = rule.captures(body)?;
+        let capture = |name: &str| captures.name(name).map(|m| m.as_str());
+
+        let fields: BTreeMap<String, String> = rule
+            .capture_names()
+            .flatten()
+            .filter(|name| !matches!(*name, "timestamp" | "level" | "message"))
+            .filter_map(|name| Some((name.to_string(), capture(name)?.to_string())))
+            .collect();
+
+        Some(LogEntry {
+            timestamp: capture("timestamp")
+                .map(str::to_string)
+                .or(fallback_timestamp),
+            timestamp_utc: None,
+            level: capture("level").map(|level| self.normalize_level(level)),
+            message: capture("message").unwrap_or(body).to_string(),
+            raw: raw.to_string(),
+            is_json: false,
+            pod_name: self.pod_name.clone(),
+            container_name: self.container_name.clone(),
+            source: LogSource::Container,
+            well_known: well_known_fields(&fields),
+            fields,
+            spans: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ParserRule;
+    use serde_json::json;
+
+    fn assignment(parser: &str) -> ParserAssignment {
+        ParserAssignment {
+            parser: parser.to_string(),
+            namespace: None,
+            workload: None,
+            container: None,
+            image: None,
+        }
+    }
+
+    fn config(assignments: Vec<ParserAssignment>) -> ParseConfig {
+        let settings = AppSettings {
+            parser_rules: vec![ParserRule {
+                name: "legacy".to_string(),
+                pattern: r"^(?P<level>\w+) \[(?P<module>\w+)\] (?P<message>.*)$".to_string(),
+            }],
+            level_aliases: BTreeMap::from([("severe".to_string(), "error".to_string())]),
+            parser_assignments: assignments,
+            ..Default::default()
+        };
+        ParseConfig::from_settings(&settings, None).unwrap()
+    }
+
+    /// Pod of the `api` Deployment with an `app` and an `envoy` container, a
+    /// `migrate` init container and a `debugger` ephemeral container
+    fn pod() -> Pod {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": "api-7d9f8-x4k2p",
+                "namespace": "shop",
+                "labels": { "pod-template-hash": "7d9f8" },
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1",
+                    "kind": "ReplicaSet",
+                    "name": "api-7d9f8",
+                    "uid": "1",
+                    "controller": true,
+                }],
+            },
+            "spec": {
+                "containers": [
+                    { "name": "app", "image": "registry.local/legacy:1.2" },
+                    { "name": "envoy", "image": "envoyproxy/envoy:v1.30" },
+                ],
+                "initContainers": [
+                    { "name": "migrate", "image": "registry.local/legacy-migrate:1.2" },
+                ],
+                "ephemeralContainers": [
+                    { "name": "debugger", "image": "busybox:1.36" },
+                ],
+            },
+        }))
+        .unwrap()
+    }
+
+    fn has_rule(config: &ParseConfig, container: &str) -> bool {
+        config
+            .parser_for(&pod(), container)
+            .apply_rule("INFO [db] ready", None, "INFO [db] ready")
+            .is_some()
+    }
+
+    #[test]
+    fn named_groups_fill_the_entry() {
+        let parser = config(vec![assignment("legacy")]).parser_for(&pod(), "app");
+        let entry = parser
+            .apply_rule("SEVERE [db] connection lost", None, "raw line")
+            .unwrap();
+
+        assert_eq!(entry.level.as_deref(), Some("ERROR"));
+        assert_eq!(entry.message, "connection lost");
+        assert_eq!(entry.raw, "raw line");
+        assert_eq!(entry.pod_name, "api-7d9f8-x4k2p");
+        assert_eq!(entry.container_name, "app");
+        assert_eq!(
+            entry.fields,
+            BTreeMap::from([("module".to_string(), "db".to_string())])
+        );
+
+        assert!(parser.apply_rule("no brackets here", None, "").is_none());
+    }
+
+    #[test]
+    fn level_aliases_are_case_insensitive() {
+        let parser = config(vec![]).parser_for(&pod(), "app");
+        assert_eq!(parser.normalize_level("Severe"), "ERROR");
+        assert_eq!(parser.normalize_level("warning"), "WARN");
+    }
+
+    #[test]
+    fn assignments_match_on_every_given_criterion() {
+        let by_image = config(vec![ParserAssignment {
+            image: Some("registry.local/legacy*".to_string()),
+            ..assignment("legacy")
+        }]);
+        assert!(has_rule(&by_image, "app"));
+        assert!(!has_rule(&by_image, "envoy"));
+        // Init and ephemeral containers are looked up too
+        assert!(has_rule(&by_image, "migrate"));
+        let debug_image = config(vec![ParserAssignment {
+            image: Some("busybox:*".to_string()),
+            ..assignment("legacy")
+        }]);
+        assert!(has_rule(&debug_image, "debugger"));
+        assert!(!has_rule(&debug_image, "app"));
+
+        // The ReplicaSet hash is dropped from the workload name
+        let by_workload = config(vec![ParserAssignment {
+            namespace: Some("shop".to_string()),
+            workload: Some("api".to_string()),
+            container: Some("a?p".to_string()),
+            ..assignment("legacy")
+        }]);
+        assert!(has_rule(&by_workload, "app"));
+        assert!(!has_rule(&by_workload, "envoy"));
+
+        let other_namespace = config(vec![ParserAssignment {
+            namespace: Some("billing".to_string()),
+            ..assignment("legacy")
+        }]);
+        assert!(!has_rule(&other_namespace, "app"));
+    }
+
+    #[test]
+    fn built_in_formats_and_plain_can_be_assigned() {
+        let klog_line = "I1026 12:00:00.000000       1 main.go:1] started";
+        let redis_line = "1:M 26 Oct 2024 12:00:00.123 * Ready";
+
+        let detected = config(vec![]).parser_for(&pod(), "app");
+        assert!(detected.parse_format(klog_line).is_some());
+        assert!(!detected.plain_only());
+
+        let forced = config(vec![assignment("redis")]).parser_for(&pod(), "app");
+        assert!(forced.parse_format(klog_line).is_none());
+        assert!(forced.parse_format(redis_line).is_some());
+
+        let plain = config(vec![assignment("plain")]).parser_for(&pod(), "app");
+        assert!(plain.plain_only());
+        assert!(plain.parse_format(klog_line).is_none());
+    }
+
+    #[test]
+    fn rejects_assignments_to_unknown_parsers() {
+        let settings = AppSettings {
+            parser_assignments: vec![assignment("missing")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            ParseConfig::from_settings(&settings, None),
+            Err(K8sError::Settings(_))
+        ));
+    }
+}
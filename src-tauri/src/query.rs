@@ -0,0 +1,579 @@
+//! Query language for log search.
+//!
+//! A query is a boolean expression over terms:
+//!
+//! - `timeout` matches entries whose message or raw line contains the word
+//! - `"connection refused"` matches a quoted phrase
+//! - `/5\d\d/` matches a regular expression
+//! - `level:ERROR`, `pod:api-*`, `container:app`, `message:retry` and
+//!   `json.user_id:42` match a single field; values may use `*` and `?` wildcards.
+//!   `json.` paths also match the keys of logfmt lines
+//!
+//! Terms are combined with `AND`, `OR`, `NOT` and parentheses. Adjacent terms
+//! without an operator are ANDed, and `AND` binds tighter than `OR`.
+
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+use crate::k8s::logs::{extract_timestamp, normalize_log_level};
+use crate::models::LogEntry;
+
+/// A query that failed to parse. `start` and `end` are character offsets into
+/// the query string so the UI can highlight the offending part.
+#[derive(Error, Debug, Clone)]
+#[error("{message}")]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        QueryError {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// A compiled query, ready to be matched against log entries
+#[derive(Debug)]
+pub struct Query {
+    root: Node,
+}
+
+impl Query {
+    /// Parse and compile a query string
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(input)?;
+        let end = input.chars().count();
+
+        if tokens.is_empty() {
+            return Err(QueryError::new("Query is empty", 0, end));
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end,
+        };
+        let root = parser.parse_or()?;
+
+        if let Some(token) = parser.peek() {
+            let message = match token.kind {
+                TokenKind::RParen => "Unmatched closing parenthesis",
+                _ => "Unexpected token",
+            };
+            return Err(QueryError::new(message, token.start, token.end));
+        }
+
+        Ok(Query { root })
+    }
+
+    /// Whether the entry satisfies the query
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let mut json = JsonCache::default();
+        self.root.matches(entry, &mut json)
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Term(Term),
+}
+
+impl Node {
+    fn matches(&self, entry: &LogEntry, json: &mut JsonCache) -> bool {
+        match self {
+            Node::And(a, b) => a.matches(entry, json) && b.matches(entry, json),
+            Node::Or(a, b) => a.matches(entry, json) || b.matches(entry, json),
+            Node::Not(a) => !a.matches(entry, json),
+            Node::Term(term) => term.matches(entry, json),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Term {
+    field: Option<Field>,
+    matcher: Matcher,
+}
+
+#[derive(Debug)]
+enum Field {
+    Level,
+    Pod,
+    Container,
+    Message,
+    /// Dot-separated path into a JSON log object
+    Json(Vec<String>),
+}
+
+#[derive(Debug)]
+enum Matcher {
+    /// Case-insensitive substring (bare words and phrases)
+    Contains(String),
+    /// Case-insensitive whole-value match with `*`/`?` wildcards
+    Glob(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn is_match(&self, value: &str) -> bool {
+        match self {
+            Matcher::Contains(needle) => value.to_lowercase().contains(needle),
+            Matcher::Glob(pattern) => glob_match(pattern, &value.to_lowercase()),
+            Matcher::Regex(re) => re.is_match(value),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, entry: &LogEntry, json: &mut JsonCache) -> bool {
+        match &self.field {
+            None => self.matcher.is_match(&entry.message) || self.matcher.is_match(&entry.raw),
+            Some(Field::Message) => self.matcher.is_match(&entry.message),
+            Some(Field::Pod) => self.matcher.is_match(&entry.pod_name),
+            Some(Field::Container) => self.matcher.is_match(&entry.container_name),
+            Some(Field::Level) => entry
+                .level
+                .as_deref()
+                .map(|level| self.matcher.is_match(level))
+                .unwrap_or(false),
+            // Leaves are in the flattened fields, only whole objects or
+            // arrays need the JSON body
+            Some(Field::Json(path)) => match entry.fields.get(&path.join(".")) {
+                Some(value) => self.matcher.is_match(value),
+                None => json
+                    .get(entry)
+                    .and_then(|value| lookup(value, path))
+                    .map(|value| self.matcher.is_match(&value))
+                    .unwrap_or(false),
+            },
+        }
+    }
+}
+
+/// Lazily parsed JSON body of the entry being matched, so queries with several
+/// `json.` terms only parse the line once
+#[derive(Default)]
+struct JsonCache {
+    parsed: Option<Option<serde_json::Value>>,
+}
+
+impl JsonCache {
+    fn get(&mut self, entry: &LogEntry) -> Option<&serde_json::Value> {
+        self.parsed
+            .get_or_insert_with(|| {
+                if !entry.is_json {
+                    return None;
+                }
+                let (_, body) = extract_timestamp(&entry.raw);
+                serde_json::from_str(body).ok()
+            })
+            .as_ref()
+    }
+}
+
+/// Resolve a dot path in a JSON value and render the result as a string
+fn lookup(value: &serde_json::Value, path: &[String]) -> Option<String> {
+    let target = path.iter().try_fold(value, |v, key| match v {
+        serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => v.get(key),
+    })?;
+
+    match target {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and `?`
+/// is any single character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(Term),
+}
+
+/// Split a query into tokens, compiling terms as they are read
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '(' || c == ')' {
+            let kind = if c == '(' {
+                TokenKind::LParen
+            } else {
+                TokenKind::RParen
+            };
+            tokens.push(Token {
+                kind,
+                start: i,
+                end: i + 1,
+            });
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+
+        // Optional `field:` prefix
+        let mut field = None;
+        if c != '"' && c != '/' {
+            let mut j = i;
+            while j < chars.len() && is_field_char(chars[j]) {
+                j += 1;
+            }
+            if j > i && j < chars.len() && chars[j] == ':' {
+                let name: String = chars[i..j].iter().collect();
+                field = Some(parse_field(&name, i, j)?);
+                i = j + 1;
+            }
+        }
+
+        let (matcher, end) = match chars.get(i) {
+            Some('"') => {
+                let (text, end) = read_delimited(&chars, i, '"', "Unterminated quoted phrase")?;
+                let matcher = if field.is_some() {
+                    Matcher::Glob(text.to_lowercase())
+                } else {
+                    Matcher::Contains(text.to_lowercase())
+                };
+                (matcher, end)
+            }
+            Some('/') => {
+                let (pattern, end) =
+                    read_delimited(&chars, i, '/', "Unterminated regular expression")?;
+                let re = RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| {
+                        QueryError::new(format!("Invalid regular expression: {}", e), i, end)
+                    })?;
+                (Matcher::Regex(re), end)
+            }
+            _ => {
+                let word_start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && chars[i] != '('
+                    && chars[i] != ')'
+                {
+                    i += 1;
+                }
+                let word: String = chars[word_start..i].iter().collect();
+
+                if word.is_empty() {
+                    return Err(QueryError::new("Missing value after field", start, i));
+                }
+
+                if field.is_none() {
+                    let keyword = match word.as_str() {
+                        "AND" | "&&" => Some(TokenKind::And),
+                        "OR" | "||" => Some(TokenKind::Or),
+                        "NOT" => Some(TokenKind::Not),
+                        _ => None,
+                    };
+                    if let Some(kind) = keyword {
+                        tokens.push(Token {
+                            kind,
+                            start,
+                            end: i,
+                        });
+                        continue;
+                    }
+                }
+
+                let matcher = match &field {
+                    None | Some(Field::Message) => Matcher::Contains(word.to_lowercase()),
+                    Some(Field::Level) => Matcher::Glob(normalize_log_level(&word).to_lowercase()),
+                    Some(_) => Matcher::Glob(word.to_lowercase()),
+                };
+                (matcher, i)
+            }
+        };
+
+        tokens.push(Token {
+            kind: TokenKind::Term(Term { field, matcher }),
+            start,
+            end,
+        });
+        i = end;
+    }
+
+    Ok(tokens)
+}
+
+fn is_field_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || c == '@'
+}
+
+fn parse_field(name: &str, start: usize, end: usize) -> Result<Field, QueryError> {
+    match name.to_lowercase().as_str() {
+        "level" => Ok(Field::Level),
+        "pod" => Ok(Field::Pod),
+        "container" => Ok(Field::Container),
+        "message" | "msg" => Ok(Field::Message),
+        _ => match name.strip_prefix("json.") {
+            Some(path) if !path.is_empty() && path.split('.').all(|p| !p.is_empty()) => {
+                Ok(Field::Json(path.split('.').map(String::from).collect()))
+            }
+            _ => Err(QueryError::new(
+                format!(
+                    "Unknown field '{}', expected level, pod, container, message or json.<path> \
+                     (quote the term to search for it literally)",
+                    name
+                ),
+                start,
+                end,
+            )),
+        },
+    }
+}
+
+/// Read text between `delim` characters starting at `open`, honouring
+/// backslash escapes of the delimiter. Returns the text and the offset after
+/// the closing delimiter.
+fn read_delimited(
+    chars: &[char],
+    open: usize,
+    delim: char,
+    unterminated: &str,
+) -> Result<(String, usize), QueryError> {
+    let mut text = String::new();
+    let mut i = open + 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&delim) => {
+                text.push(delim);
+                i += 2;
+            }
+            c if c == delim => return Ok((text, i + 1)),
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Err(QueryError::new(unterminated, open, chars.len()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<Node, QueryError> {
+        let mut node = self.parse_and()?;
+        while matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Or)) {
+            self.pos += 1;
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, QueryError> {
+        let mut node = self.parse_not()?;
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::And) => self.pos += 1,
+                // Juxtaposed terms are an implicit AND
+                Some(TokenKind::Term(_)) | Some(TokenKind::Not) | Some(TokenKind::LParen) => {}
+                _ => return Ok(node),
+            }
+            node = Node::And(Box::new(node), Box::new(self.parse_not()?));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Node, QueryError> {
+        if matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Not)) {
+            self.pos += 1;
+            return Ok(Node::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, QueryError> {
+        let Some(token) = self.tokens.get_mut(self.pos) else {
+            return Err(QueryError::new(
+                "Unexpected end of query",
+                self.end,
+                self.end,
+            ));
+        };
+        let (start, end) = (token.start, token.end);
+
+        match std::mem::replace(&mut token.kind, TokenKind::RParen) {
+            TokenKind::Term(term) => {
+                self.pos += 1;
+                Ok(Node::Term(term))
+            }
+            TokenKind::LParen => {
+                self.pos += 1;
+                let node = self.parse_or()?;
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => {
+                        self.pos += 1;
+                        Ok(node)
+                    }
+                    _ => Err(QueryError::new("Unclosed parenthesis", start, end)),
+                }
+            }
+            _ => Err(QueryError::new("Expected a search term", start, end)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, message: &str, level: &str) -> bool {
+        let entry = LogEntry {
+            level: Some(level.to_string()),
+            ..LogEntry::from_message(message)
+        };
+        Query::parse(query).unwrap().matches(&entry)
+    }
+
+    fn span(query: &str) -> (usize, usize) {
+        let e = Query::parse(query).unwrap_err();
+        (e.start, e.end)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // timeout OR (refused AND level:ERROR)
+        assert!(matches("timeout OR refused level:ERROR", "timeout", "INFO"));
+        assert!(!matches(
+            "timeout OR refused level:ERROR",
+            "refused",
+            "INFO"
+        ));
+        assert!(matches(
+            "timeout OR refused level:ERROR",
+            "refused",
+            "ERROR"
+        ));
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        assert!(!matches(
+            "(timeout OR refused) level:ERROR",
+            "timeout",
+            "INFO"
+        ));
+        assert!(matches(
+            "(timeout OR refused) level:ERROR",
+            "timeout",
+            "ERROR"
+        ));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term_only() {
+        assert!(matches("NOT timeout refused", "refused", "INFO"));
+        assert!(!matches("NOT timeout refused", "timeout refused", "INFO"));
+        assert!(matches("NOT (timeout refused)", "timeout", "INFO"));
+    }
+
+    #[test]
+    fn phrases_globs_and_regexes() {
+        assert!(matches(
+            "\"connection refused\"",
+            "dial: Connection refused",
+            "INFO"
+        ));
+        assert!(!matches(
+            "\"connection refused\"",
+            "connection was refused",
+            "INFO"
+        ));
+        assert!(matches("pod:api-* container:app", "x", "INFO"));
+        assert!(matches("level:warning", "x", "WARN"));
+        assert!(matches("/status=5\\d\\d/", "status=503", "INFO"));
+    }
+
+    #[test]
+    fn error_spans_point_at_the_offending_part() {
+        assert_eq!(span("  "), (0, 2));
+        assert_eq!(span("a )"), (2, 3));
+        assert_eq!(span("(a OR b"), (0, 1));
+        assert_eq!(span("a \"open"), (2, 7));
+        assert_eq!(span("a /[/"), (2, 5));
+        assert_eq!(span("foo:bar"), (0, 3));
+        // At the end of the query, where a term is missing
+        assert_eq!(span("a OR"), (4, 4));
+        assert_eq!(span("a AND OR b"), (6, 8));
+    }
+
+    #[test]
+    fn error_offsets_count_characters() {
+        assert_eq!(span("héllo )"), (6, 7));
+    }
+}
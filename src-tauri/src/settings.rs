@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use tauri::State;
+
+use crate::error::K8sError;
+use crate::models::AppSettings;
+use crate::parser::ParseConfig;
+use crate::state::AppState;
+
+/// Location of the settings file
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("podlogs").join("settings.json"))
+}
+
+/// Load the settings from disk, falling back to defaults when there is no
+/// settings file yet. A file that exists but doesn't parse is an error rather
+/// than silently replaced by the defaults.
+pub fn load_settings() -> Result<AppSettings, K8sError> {
+    let Some(path) = settings_path() else {
+        return Ok(AppSettings::default());
+    };
+
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AppSettings::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    serde_json::from_str(&data)
+        .map_err(|e| K8sError::Settings(format!("Invalid settings file {}: {e}", path.display())))
+}
+
+/// Parsed settings, read from disk on first use and replaced on every update,
+/// so fetches and streams don't re-read the settings file
+#[derive(Default)]
+pub struct SettingsCache {
+    cached: Mutex<Option<Cached>>,
+}
+
+struct Cached {
+    settings: Arc<AppSettings>,
+    /// Parser rules compiled from `settings` on first use. Not compiled up
+    /// front, so a hand-edited file with a bad rule can still be loaded and
+    /// fixed from the UI.
+    parse_config: Option<ParseConfig>,
+}
+
+impl SettingsCache {
+    /// The current settings, loading them from disk if not cached yet
+    pub fn get(&self) -> Result<Arc<AppSettings>, K8sError> {
+        let mut cached = self.cached.lock().unwrap();
+        Ok(Self::loaded(&mut cached)?.settings.clone())
+    }
+
+    /// The parser rules of the current settings, compiled once per change
+    pub(crate) fn parse_config(&self) -> Result<ParseConfig, K8sError> {
+        let mut cached = self.cached.lock().unwrap();
+        let cached = Self::loaded(&mut cached)?;
+        if let Some(config) = &cached.parse_config {
+            return Ok(config.clone());
+        }
+
+        let config = ParseConfig::from_settings(&cached.settings, None)?;
+        cached.parse_config = Some(config.clone());
+        Ok(config)
+    }
+
+    fn loaded(cached: &mut Option<Cached>) -> Result<&mut Cached, K8sError> {
+        match cached {
+            Some(cached) => Ok(cached),
+            None => Ok(cached.insert(Cached {
+                settings: Arc::new(load_settings()?),
+                parse_config: None,
+            })),
+        }
+    }
+
+    fn set(&self, settings: AppSettings, parse_config: ParseConfig) {
+        *self.cached.lock().unwrap() = Some(Cached {
+            settings: Arc::new(settings),
+            parse_config: Some(parse_config),
+        });
+    }
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, K8sError> {
+    Ok(state.settings.get()?.as_ref().clone())
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    state: State<'_, AppState>,
+    settings: AppSettings,
+) -> Result<AppSettings, K8sError> {
+    let path = settings_path().ok_or_else(|| {
+        K8sError::Settings("Could not determine the config directory".to_string())
+    })?;
+
+    // Refuse rules that would fail every later fetch
+    let parse_config = ParseConfig::from_settings(&settings, None)?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let data =
+        serde_json::to_string_pretty(&settings).map_err(|e| K8sError::Settings(e.to_string()))?;
+    std::fs::write(&path, data)?;
+    state.settings.set(settings.clone(), parse_config);
+
+    Ok(settings)
+}
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use crate::k8s::client::ClientPool;
+use crate::k8s::tasks::TaskRegistry;
+use crate::settings::SettingsCache;
+
+/// Backend state shared by all commands, managed by Tauri
+pub struct AppState {
+    pub clients: ClientPool,
+    pub tasks: TaskRegistry,
+    /// Shared with `clients`, which load contexts from the extra kubeconfig
+    /// files it lists
+    pub settings: Arc<SettingsCache>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        let settings = Arc::new(SettingsCache::default());
+        Self {
+            clients: ClientPool::new(settings.clone()),
+            tasks: TaskRegistry::default(),
+            settings,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Normalization of the many timestamp spellings found in logs to UTC.
+
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeDelta, Utc};
+
+/// Formats with an explicit offset tried after RFC 3339
+const OFFSET_FORMATS: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%d/%b/%Y:%H:%M:%S %z",
+];
+
+/// Formats without an offset, i.e. in the container's local time
+const LOCAL_FORMATS: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+];
+
+/// Format of klog's `MMDD hh:mm:ss.uuuuuu` local times once prefixed with a year
+const YEARLESS_FORMAT: &str = "%Y%m%d %H:%M:%S%.f";
+
+/// Epoch values below this many seconds (early 1973) are more likely
+/// durations or counters than times
+const MIN_EPOCH_SECONDS: i64 = 100_000_000;
+
+/// Parse a timestamp as written in a log line: RFC 3339, epoch seconds,
+/// milliseconds, microseconds or nanoseconds (told apart by magnitude), or a
+/// date and time with or without an offset.
+///
+/// Times without an offset are local to the container. When `reference` (the
+/// kubelet's timestamp for the same line) is known, the container's UTC
+/// offset is inferred from it; otherwise they are taken as UTC. Likewise the
+/// year of klog's `MMDD hh:mm:ss.uuuuuu` times is taken from `reference` or now.
+pub fn parse(value: &str, reference: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Some(time) = parse_epoch(value) {
+        return Some(time);
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.to_utc());
+    }
+    if let Some(time) = OFFSET_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(value, format).ok())
+    {
+        return Some(time.to_utc());
+    }
+
+    // Python's logging writes `2024-01-01 12:00:00,123`
+    let value = value.replace(',', ".");
+    let local = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&value, format).ok())
+        .or_else(|| parse_yearless(&value, reference))?;
+
+    let offset = reference
+        .map(|r| local_offset(local, r))
+        .unwrap_or_default();
+    Some(local.and_utc() - offset)
+}
+
+/// Format a time as RFC 3339 UTC with nanoseconds. Every value has the same
+/// width, so they sort as strings.
+pub fn format(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.9fZ").to_string()
+}
+
+/// Parse a time without a year in the year that puts it closest to
+/// `reference`, so December lines read in January keep their year
+fn parse_yearless(value: &str, reference: Option<DateTime<Utc>>) -> Option<NaiveDateTime> {
+    let reference = reference.unwrap_or_else(Utc::now).naive_utc();
+    let year = reference.year();
+
+    (year - 1..=year + 1)
+        .filter_map(|year| {
+            NaiveDateTime::parse_from_str(&format!("{year}{value}"), YEARLESS_FORMAT).ok()
+        })
+        .min_by_key(|time| (*time - reference).abs())
+}
+
+/// Offset between a local time and the UTC time it was logged at, rounded to
+/// the quarter hour since both were taken a few moments apart
+fn local_offset(local: NaiveDateTime, reference: DateTime<Utc>) -> TimeDelta {
+    const QUARTER: i64 = 15 * 60;
+
+    let seconds = (local.and_utc() - reference).num_seconds();
+    let quarters = (seconds as f64 / QUARTER as f64).round() as i64;
+
+    // Real offsets range from -12:00 to +14:00; anything else means the line
+    // wasn't written when the kubelet saw it
+    if (-48..=56).contains(&quarters) {
+        TimeDelta::seconds(quarters * QUARTER)
+    } else {
+        TimeDelta::zero()
+    }
+}
+
+/// Parse an epoch number, optionally with a fractional part
+fn parse_epoch(value: &str) -> Option<DateTime<Utc>> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty()
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let whole: i64 = whole.parse().ok()?;
+    if whole < MIN_EPOCH_SECONDS {
+        return None;
+    }
+
+    // Digits of a nanosecond count held by one unit of the value
+    let unit_digits: u32 = match whole {
+        0..100_000_000_000 => 9,
+        100_000_000_000..100_000_000_000_000 => 6,
+        100_000_000_000_000..100_000_000_000_000_000 => 3,
+        _ => 0,
+    };
+
+    let fraction: String = fraction
+        .chars()
+        .chain(std::iter::repeat('0'))
+        .take(unit_digits as usize)
+        .collect();
+    let nanos = i128::from(whole) * 10i128.pow(unit_digits) + fraction.parse::<i128>().unwrap_or(0);
+
+    Some(DateTime::from_timestamp_nanos(i64::try_from(nanos).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str, reference: Option<&str>) -> Option<String> {
+        let reference = reference.map(|r| parse(r, None).unwrap());
+        parse(value, reference).map(format)
+    }
+
+    #[test]
+    fn rfc3339_with_any_offset() {
+        assert_eq!(
+            utc("2024-10-26T12:00:00.123456789Z", None).as_deref(),
+            Some("2024-10-26T12:00:00.123456789Z")
+        );
+        assert_eq!(
+            utc("2024-10-26T14:00:00+02:00", None).as_deref(),
+            Some("2024-10-26T12:00:00.000000000Z")
+        );
+        assert_eq!(
+            utc("26/Oct/2024:14:00:00 +0200", None).as_deref(),
+            Some("2024-10-26T12:00:00.000000000Z")
+        );
+    }
+
+    #[test]
+    fn epoch_units_by_magnitude() {
+        let expected = Some("2024-10-26T12:00:00.500000000Z");
+        assert_eq!(utc("1729944000.5", None).as_deref(), expected);
+        assert_eq!(utc("1729944000500", None).as_deref(), expected);
+        assert_eq!(utc("1729944000500000", None).as_deref(), expected);
+        assert_eq!(utc("1729944000500000000", None).as_deref(), expected);
+        // Too small to be a time
+        assert_eq!(utc("4200", None), None);
+    }
+
+    #[test]
+    fn local_times_take_the_offset_of_the_reference() {
+        let kubelet = Some("2024-10-26T12:00:00.2Z");
+        assert_eq!(
+            utc("2024-10-26 14:00:00,123", kubelet).as_deref(),
+            Some("2024-10-26T12:00:00.123000000Z")
+        );
+        assert_eq!(
+            utc("2024-10-26 14:00:00.123", None).as_deref(),
+            Some("2024-10-26T14:00:00.123000000Z")
+        );
+        // Hours apart from the kubelet's time: not an offset
+        assert_eq!(
+            utc("2024-10-20 14:00:00", kubelet).as_deref(),
+            Some("2024-10-20T14:00:00.000000000Z")
+        );
+    }
+
+    #[test]
+    fn klog_times_take_the_year_of_the_reference() {
+        assert_eq!(
+            utc("1026 12:00:00.000001", Some("2023-10-26T12:00:00Z")).as_deref(),
+            Some("2023-10-26T12:00:00.000001000Z")
+        );
+        // Logged on New Year's Eve, read just after midnight
+        assert_eq!(
+            utc("1231 23:59:59.5", Some("2025-01-01T00:00:01Z")).as_deref(),
+            Some("2024-12-31T23:59:59.500000000Z")
+        );
+        assert_eq!(
+            utc("0101 01:00:01", Some("2024-12-31T23:59:59Z")).as_deref(),
+            Some("2025-01-01T00:00:01.000000000Z")
+        );
+    }
+
+    #[test]
+    fn rejects_other_text() {
+        assert_eq!(utc("yesterday", None), None);
+        assert_eq!(utc("13-45 12:00:00", None), None);
+    }
+}
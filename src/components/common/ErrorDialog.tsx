@@ -1,6 +1,7 @@
 import { AlertTriangle, RefreshCw, X } from 'lucide-react';
 import { Button } from './Button';
 import { Modal } from './Modal';
+import type { K8sError } from '../../types/kubernetes';
 
 interface ErrorDialogProps {
   isOpen: boolean;
@@ -65,6 +66,8 @@ export function ConnectionError({ error, onDismiss, onRetry }: ConnectionErrorPr
   if (!error) return null;
 
   const errorMessage = error.message || 'Unknown error occurred';
+  // Backend errors carry a kind, errors raised in the frontend don't
+  const kind = (error as Partial<K8sError>).kind;
 
   // Parse common K8s errors for user-friendly messages
   let userMessage = 'Failed to connect to Kubernetes cluster';
@@ -73,21 +76,21 @@ export function ConnectionError({ error, onDismiss, onRetry }: ConnectionErrorPr
   if (errorMessage.includes('No kubeconfig found')) {
     userMessage = 'Kubernetes configuration not found';
     details = 'Please ensure kubectl is configured and ~/.kube/config exists.';
-  } else if (errorMessage.includes('connection refused') || errorMessage.includes('ECONNREFUSED')) {
+  } else if (errorMessage.includes('certificate')) {
+    userMessage = 'Certificate error';
+    details = 'There was a problem with the cluster certificate. ' + errorMessage;
+  } else if (kind === 'Connection' || errorMessage.includes('connection refused') || errorMessage.includes('ECONNREFUSED')) {
     userMessage = 'Cannot reach Kubernetes cluster';
     details = 'The cluster may be offline or unreachable. Check your network connection and cluster status.';
-  } else if (errorMessage.includes('Unauthorized') || errorMessage.includes('401')) {
+  } else if (kind === 'Unauthorized' || kind === 'AuthPlugin' || errorMessage.includes('Unauthorized') || errorMessage.includes('401')) {
     userMessage = 'Authentication failed';
     details = 'Your Kubernetes credentials may have expired. Try running: kubectl get pods';
-  } else if (errorMessage.includes('Forbidden') || errorMessage.includes('403')) {
+  } else if (kind === 'Forbidden' || errorMessage.includes('Forbidden') || errorMessage.includes('403')) {
     userMessage = 'Access denied';
     details = 'You do not have permission to access this resource. Check your RBAC settings.';
-  } else if (errorMessage.includes('timeout') || errorMessage.includes('ETIMEDOUT')) {
+  } else if (kind === 'Timeout' || errorMessage.includes('timeout') || errorMessage.includes('ETIMEDOUT')) {
     userMessage = 'Connection timed out';
     details = 'The cluster took too long to respond. Check your network connection.';
-  } else if (errorMessage.includes('certificate')) {
-    userMessage = 'Certificate error';
-    details = 'There was a problem with the cluster certificate. ' + errorMessage;
   }
 
   return (
@@ -79,10 +79,14 @@ export function DeploymentDetails({ details }: DeploymentDetailsProps) {
       {/* Selector */}
       <Section title="Selector">
         <div className="flex flex-wrap gap-2">
-          {Object.entries(details.selector).map(([key, value]) => (
-            <LabelBadge key={key} label={`${key}=${value}`} />
-          ))}
-          {Object.keys(details.selector).length === 0 && (
+          {/* One badge per requirement; commas inside `in (a,b)` don't split */}
+          {details.selector_string
+            .split(/,(?![^(]*\))/)
+            .filter(Boolean)
+            .map((requirement) => (
+              <LabelBadge key={requirement} label={requirement} />
+            ))}
+          {details.selector_string === '' && (
             <span className="text-text-muted text-sm">No selectors</span>
           )}
         </div>
@@ -62,8 +62,11 @@ export function LogDetailModal({ entry, onClose }: LogDetailModalProps) {
           <div>
             <span className="text-text-muted">Timestamp</span>
             <p className="font-mono text-text-primary mt-1">
-              {formatTimestamp(entry.timestamp)}
+              {formatTimestamp(entry.timestamp_utc ?? entry.timestamp)}
             </p>
+            {entry.timestamp && entry.timestamp !== entry.timestamp_utc && (
+              <p className="font-mono text-xs text-text-muted mt-0.5">{entry.timestamp}</p>
+            )}
           </div>
           <div>
             <span className="text-text-muted">Level</span>
@@ -134,6 +137,21 @@ export function LogDetailModal({ entry, onClose }: LogDetailModalProps) {
           </div>
         </div>
 
+        {/* Structured fields of logfmt lines, JSON lines are shown below */}
+        {!entry.is_json && Object.keys(entry.fields).length > 0 && (
+          <div>
+            <span className="text-sm text-text-muted">Fields</span>
+            <div className="mt-2 p-3 rounded-md bg-bg-secondary border border-border font-mono text-xs max-h-[200px] overflow-auto">
+              {Object.entries(entry.fields).map(([key, value]) => (
+                <div key={key} className="flex gap-2">
+                  <span className="text-text-muted shrink-0">{key}</span>
+                  <span className="text-text-secondary break-all">{value}</span>
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {/* JSON View (if applicable) */}
         {entry.is_json && (
           <div>
@@ -57,7 +57,7 @@ export function LogSearch() {
   }, [podColumnWidth]);
 
   const {
-    data: results = [],
+    data: response,
     isLoading,
     isFetching,
   } = useLogSearch(
@@ -65,6 +65,7 @@ export function LogSearch() {
     { keyword, logLevel, timeRange },
     searchTriggered && !!selectedDeployment
   );
+  const results = response?.results ?? [];
 
   const deploymentOptions = useMemo(
     () => deployments.map((d) => ({ value: d.name, label: d.name })),
@@ -93,7 +94,7 @@ export function LogSearch() {
   const handleExport = () => {
     const lines = results.flatMap((r) =>
       r.entries.map(
-        (e) => `${e.timestamp}\t${r.pod_name}\t${e.level || '-'}\t${e.message}`
+        (e) => `${e.timestamp_utc ?? e.timestamp}\t${r.pod_name}\t${e.level || '-'}\t${e.message}`
       )
     );
     const content = lines.join('\n');
@@ -232,7 +233,7 @@ export function LogSearch() {
                         onClick={() => setSelectedLog({ ...entry, pod_name: result.pod_name, container_name: result.container_name })}
                       >
                         <td className="px-4 py-2 font-mono text-sm text-text-muted w-24">
-                          {formatShortTimestamp(entry.timestamp)}
+                          {formatShortTimestamp(entry.timestamp_utc ?? entry.timestamp)}
                         </td>
                         <td className="px-4 py-2" style={{ width: podColumnWidth }}>
                           <span
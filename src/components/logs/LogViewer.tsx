@@ -13,7 +13,7 @@ import { usePodDetails } from '../../hooks/useK8s';
 import { useUIStore } from '../../stores/uiStore';
 import { formatTimestamp } from '../../lib/formatters';
 import { TIME_RANGES, getTimeRangeLabel, type TimeRange } from '../../lib/tauri';
-import type { LogEntry } from '../../types/logs';
+import type { LogEntry, StyledSpan } from '../../types/logs';
 
 interface LogViewerProps {
   podName: string | null;
@@ -303,11 +303,16 @@ function LogRow({ entry, viewMode, style, onClick }: LogRowProps) {
       )}
     >
       <span className="w-44 text-text-muted shrink-0">
-        {formatTimestamp(entry.timestamp)}
+        {formatTimestamp(entry.timestamp_utc ?? entry.timestamp)}
       </span>
       <span className="w-16 shrink-0">
         <LogLevelBadge level={entry.level} />
       </span>
+      {entry.source === 'event' && (
+        <span className="mr-2 px-1.5 rounded text-xs font-medium bg-violet-500/10 text-violet-600 dark:text-violet-400 shrink-0">
+          EVENT
+        </span>
+      )}
       <span
         className={clsx(
           'flex-1 truncate',
@@ -316,9 +321,56 @@ function LogRow({ entry, viewMode, style, onClick }: LogRowProps) {
           !entry.level && 'text-text-secondary'
         )}
       >
-        {entry.message}
+        {entry.spans && !entry.is_json ? <StyledText spans={entry.spans} /> : entry.message}
       </span>
     </div>
   );
 }
 
+// Terminal palette (VS Code dark) for the basic ANSI color names
+const ANSI_COLORS: Record<string, string> = {
+  black: '#000000',
+  red: '#cd3131',
+  green: '#0dbc79',
+  yellow: '#e5e510',
+  blue: '#2472c8',
+  magenta: '#bc3fbc',
+  cyan: '#11a8cd',
+  white: '#e5e5e5',
+  bright_black: '#666666',
+  bright_red: '#f14c4c',
+  bright_green: '#23d18b',
+  bright_yellow: '#f5f543',
+  bright_blue: '#3b8eea',
+  bright_magenta: '#d670d6',
+  bright_cyan: '#29b8db',
+  bright_white: '#ffffff',
+};
+
+function ansiColor(color: string | null): string | undefined {
+  if (!color) return undefined;
+  return color.startsWith('#') ? color : ANSI_COLORS[color];
+}
+
+function StyledText({ spans }: { spans: StyledSpan[] }) {
+  return (
+    <>
+      {spans.map((span, i) => (
+        <span
+          key={i}
+          style={{
+            color: ansiColor(span.fg),
+            backgroundColor: ansiColor(span.bg),
+            fontWeight: span.bold ? 'bold' : undefined,
+            opacity: span.dim ? 0.7 : undefined,
+            fontStyle: span.italic ? 'italic' : undefined,
+            textDecoration: span.underline ? 'underline' : undefined,
+          }}
+        >
+          {span.text}
+        </span>
+      ))}
+    </>
+  );
+}
+
@@ -1,3 +1,4 @@
+import { useEffect } from 'react';
 import { useQuery, useQueryClient } from '@tanstack/react-query';
 import * as k8s from '../lib/tauri';
 import { useClusterStore } from '../stores/clusterStore';
@@ -24,6 +25,16 @@ export function useClusters() {
   const queryClient = useQueryClient();
   const queryKey = ['clusters'];
 
+  // Pick up kubeconfig edits without waiting for a refetch
+  useEffect(() => {
+    const unlisten = k8s.onClustersChanged((clusters) => {
+      queryClient.setQueryData(['clusters'], clusters);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [queryClient]);
+
   return useQuery({
     queryKey,
     queryFn: async () => {
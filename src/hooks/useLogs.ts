@@ -1,8 +1,9 @@
-import { useQuery } from '@tanstack/react-query';
+import { useQuery, useQueryClient } from '@tanstack/react-query';
 import * as k8s from '../lib/tauri';
 import { useClusterStore } from '../stores/clusterStore';
 import type { TimeRange } from '../lib/tauri';
-import { LOG_REFETCH_INTERVAL } from '../lib/constants';
+import type { LogPage } from '../types/logs';
+import { LOG_REFETCH_INTERVAL, MAX_LOG_LINES } from '../lib/constants';
 
 export function usePodLogs(
   podName: string | null,
@@ -13,14 +14,26 @@ export function usePodLogs(
   }
 ) {
   const { context, namespace } = useClusterStore();
+  const queryClient = useQueryClient();
+  const queryKey = ['pod-logs', context, namespace, podName, options.container, options.timeRange];
 
+  // Each refetch asks only for the lines after the previous page's cursor and
+  // appends them, so lines are neither repeated nor lost between ticks. Only
+  // the newest MAX_LOG_LINES entries are kept. Pages are grouped on their own,
+  // so a stack trace cut by a tick shows its continuation lines as entries of
+  // their own.
   return useQuery({
-    queryKey: ['pod-logs', context, namespace, podName, options.container, options.timeRange],
-    queryFn: () =>
-      k8s.getPodLogs(context, namespace, podName!, {
+    queryKey,
+    queryFn: async (): Promise<LogPage> => {
+      const previous = queryClient.getQueryData<LogPage>(queryKey);
+      const page = await k8s.pollPodLogs(context, namespace, podName!, previous?.cursor ?? null, {
         container: options.container,
         sinceSeconds: k8s.TIME_RANGES[options.timeRange],
-      }),
+      });
+      const entries = previous ? [...previous.entries, ...page.entries] : page.entries;
+      return { entries: entries.slice(-MAX_LOG_LINES), cursor: page.cursor };
+    },
+    select: (page) => page.entries,
     enabled: options.enabled !== false && !!context && !!namespace && !!podName,
     refetchInterval: LOG_REFETCH_INTERVAL,
   });
@@ -42,8 +55,8 @@ export function useLogSearch(
     queryFn: () =>
       k8s.searchDeploymentLogs(context, namespace, deployment!, {
         keyword: filters.keyword || undefined,
-        logLevel: filters.logLevel || undefined, // Convert empty string to undefined for "Any"
-        sinceSeconds: k8s.TIME_RANGES[filters.timeRange],
+        log_level: filters.logLevel || undefined, // Convert empty string to undefined for "Any"
+        since_seconds: k8s.TIME_RANGES[filters.timeRange],
       }),
     enabled: enabled && !!context && !!namespace && !!deployment,
   });
@@ -1,5 +1,7 @@
 export const REFETCH_INTERVAL = 5000; // 5 seconds
 export const LOG_REFETCH_INTERVAL = 5000;
+// Polled pod logs keep only this many of the newest entries
+export const MAX_LOG_LINES = 10000;
 
 export const SIDEBAR_WIDTH = 260;
 export const SIDEBAR_COLLAPSED_WIDTH = 60;
@@ -1,4 +1,5 @@
-import { invoke } from '@tauri-apps/api/core';
+import { Channel, invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
   ClusterInfo,
   NamespaceInfo,
@@ -6,8 +7,24 @@ import type {
   DeploymentDetails,
   PodInfo,
   PodDetails,
+  WorkloadDetails,
+  WorkloadInfo,
+  WorkloadKind,
+  K8sError,
 } from '../types/kubernetes';
-import type { LogEntry, LogSearchResult } from '../types/logs';
+import type {
+  LogEntry,
+  LogPage,
+  LogSearchOptions,
+  LogSearchResponse,
+  LogStreamEvent,
+  LogStreamOptions,
+  MultilineOptions,
+} from '../types/logs';
+import type { AppSettings } from '../types/settings';
+import type { EventStreamEvent, EventTarget, KubeEvent } from '../types/events';
+import type { PodMetrics } from '../types/metrics';
+import type { CompareOptions, LogComparison, LogSelection } from '../types/compare';
 
 // Timeout for K8s API calls (30 seconds)
 const API_TIMEOUT = 30000;
@@ -38,6 +55,13 @@ export async function getClusters(): Promise<ClusterInfo[]> {
   return invoke<ClusterInfo[]>('get_clusters');
 }
 
+// Fired by the backend when a kubeconfig file is edited
+export async function onClustersChanged(
+  handler: (clusters: ClusterInfo[]) => void
+): Promise<UnlistenFn> {
+  return listen<ClusterInfo[]>('clusters-changed', (event) => handler(event.payload));
+}
+
 // ============================================
 // Namespace Operations
 // ============================================
@@ -69,6 +93,32 @@ export async function getDeploymentDetails(
   });
 }
 
+// ============================================
+// Workload Operations
+// ============================================
+
+export async function getWorkloads(
+  context: string,
+  namespace: string,
+  kind: WorkloadKind
+): Promise<WorkloadInfo[]> {
+  return invokeWithTimeout<WorkloadInfo[]>('get_workloads', { context, namespace, kind });
+}
+
+export async function getWorkloadDetails(
+  context: string,
+  namespace: string,
+  kind: WorkloadKind,
+  name: string
+): Promise<WorkloadDetails> {
+  return invokeWithTimeout<WorkloadDetails>('get_workload_details', {
+    context,
+    namespace,
+    kind,
+    name,
+  });
+}
+
 // ============================================
 // Pod Operations
 // ============================================
@@ -81,6 +131,24 @@ export async function getPods(
   return invokeWithTimeout<PodInfo[]>('get_pods', { context, namespace, deployment });
 }
 
+export async function getWorkloadPods(
+  context: string,
+  namespace: string,
+  kind: WorkloadKind,
+  name: string
+): Promise<PodInfo[]> {
+  return invokeWithTimeout<PodInfo[]>('get_workload_pods', { context, namespace, kind, name });
+}
+
+// selector is a label selector such as "app=api,tier in (web,worker)"
+export async function getPodsBySelector(
+  context: string,
+  namespace: string,
+  selector: string
+): Promise<PodInfo[]> {
+  return invokeWithTimeout<PodInfo[]>('get_pods_by_selector', { context, namespace, selector });
+}
+
 export async function getPodDetails(
   context: string,
   namespace: string,
@@ -104,39 +172,291 @@ export async function getPodLogs(
   options?: {
     container?: string;
     sinceSeconds?: number;
+    // RFC 3339 bounds of an absolute range; sinceTime excludes sinceSeconds
+    sinceTime?: string;
+    untilTime?: string;
     tailLines?: number;
+    previous?: boolean;
+    multiline?: MultilineOptions;
+    // Interleave the pod's events within the range
+    includeEvents?: boolean;
   }
 ): Promise<LogEntry[]> {
   return invokeWithTimeout<LogEntry[]>('get_pod_logs', {
     context,
     namespace,
     podName,
-    container: options?.container,
-    sinceSeconds: options?.sinceSeconds,
-    tailLines: options?.tailLines,
+    options: {
+      container: options?.container,
+      since_seconds: options?.sinceSeconds,
+      since_time: options?.sinceTime,
+      until_time: options?.untilTime,
+      tail_lines: options?.tailLines,
+      previous: options?.previous,
+      multiline: options?.multiline,
+      include_events: options?.includeEvents,
+    },
   });
 }
 
-export async function searchDeploymentLogs(
+// Fetch only the lines logged since `cursor` (from the previous page). The
+// first call, without a cursor, honors the range and tail options.
+export async function pollPodLogs(
   context: string,
   namespace: string,
-  deployment: string,
+  podName: string,
+  cursor: string | null,
   options?: {
-    keyword?: string;
-    logLevel?: string;
+    container?: string;
     sinceSeconds?: number;
+    tailLines?: number;
+    multiline?: MultilineOptions;
   }
-): Promise<LogSearchResult[]> {
-  return invokeWithTimeout<LogSearchResult[]>('search_deployment_logs', {
+): Promise<LogPage> {
+  return invokeWithTimeout<LogPage>('poll_pod_logs', {
+    context,
+    namespace,
+    podName,
+    cursor,
+    options: {
+      container: options?.container,
+      since_seconds: options?.sinceSeconds,
+      tail_lines: options?.tailLines,
+      multiline: options?.multiline,
+    },
+  });
+}
+
+export async function searchDeploymentLogs(
+  context: string,
+  namespace: string,
+  deployment: string,
+  options: LogSearchOptions = {}
+): Promise<LogSearchResponse> {
+  // Searches can legitimately outlive the API timeout, cancel them with cancelSearch instead
+  return invoke<LogSearchResponse>('search_deployment_logs', {
+    context,
+    namespace,
+    deployment,
+    options,
+  });
+}
+
+export async function searchWorkloadLogs(
+  context: string,
+  namespace: string,
+  kind: WorkloadKind,
+  name: string,
+  options: LogSearchOptions = {}
+): Promise<LogSearchResponse> {
+  return invoke<LogSearchResponse>('search_workload_logs', {
+    context,
+    namespace,
+    kind,
+    name,
+    options,
+  });
+}
+
+// Search only the pods of one rollout revision of a deployment
+export async function searchRevisionLogs(
+  context: string,
+  namespace: string,
+  deployment: string,
+  revision: number,
+  options: LogSearchOptions = {}
+): Promise<LogSearchResponse> {
+  return invoke<LogSearchResponse>('search_revision_logs', {
+    context,
+    namespace,
+    deployment,
+    revision,
+    options,
+  });
+}
+
+export async function searchSelectorLogs(
+  context: string,
+  namespace: string,
+  selector: string,
+  options: LogSearchOptions = {}
+): Promise<LogSearchResponse> {
+  return invoke<LogSearchResponse>('search_selector_logs', {
+    context,
+    namespace,
+    selector,
+    options,
+  });
+}
+
+export async function cancelSearch(searchId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_search', { searchId });
+}
+
+// Resolves to null when the query is valid, else to an InvalidQuery error with its span
+export async function validateLogQuery(query: string): Promise<K8sError | null> {
+  try {
+    await invoke<void>('validate_log_query', { query });
+    return null;
+  } catch (e) {
+    return e as K8sError;
+  }
+}
+
+// Compare two selections of logs, e.g. two revisions after a rollout. Fetches
+// every container of both sides, so it may outlive the API timeout.
+export async function compareLogs(
+  context: string,
+  namespace: string,
+  baseline: LogSelection,
+  candidate: LogSelection,
+  options: CompareOptions = {}
+): Promise<LogComparison> {
+  return invoke<LogComparison>('compare_logs', {
+    context,
+    namespace,
+    baseline,
+    candidate,
+    options,
+  });
+}
+
+// ============================================
+// Log Streaming
+// ============================================
+
+export async function startLogStream(
+  streamId: string,
+  context: string,
+  namespace: string,
+  podName: string,
+  onEvent: (event: LogStreamEvent) => void,
+  options: LogStreamOptions = {}
+): Promise<void> {
+  const channel = new Channel<LogStreamEvent>();
+  channel.onmessage = onEvent;
+  return invokeWithTimeout<void>('start_log_stream', {
+    streamId,
+    context,
+    namespace,
+    podName,
+    options,
+    onEvent: channel,
+  });
+}
+
+export async function startDeploymentLogStream(
+  streamId: string,
+  context: string,
+  namespace: string,
+  deployment: string,
+  onEvent: (event: LogStreamEvent) => void,
+  options: LogStreamOptions = {}
+): Promise<void> {
+  const channel = new Channel<LogStreamEvent>();
+  channel.onmessage = onEvent;
+  return invokeWithTimeout<void>('start_deployment_log_stream', {
+    streamId,
     context,
     namespace,
     deployment,
-    keyword: options?.keyword,
-    logLevel: options?.logLevel,
-    sinceSeconds: options?.sinceSeconds,
+    options,
+    onEvent: channel,
   });
 }
 
+export async function startSelectorLogStream(
+  streamId: string,
+  context: string,
+  namespace: string,
+  selector: string,
+  onEvent: (event: LogStreamEvent) => void,
+  options: LogStreamOptions = {}
+): Promise<void> {
+  const channel = new Channel<LogStreamEvent>();
+  channel.onmessage = onEvent;
+  return invokeWithTimeout<void>('start_selector_log_stream', {
+    streamId,
+    context,
+    namespace,
+    selector,
+    options,
+    onEvent: channel,
+  });
+}
+
+export async function stopLogStream(streamId: string): Promise<boolean> {
+  return invoke<boolean>('stop_log_stream', { streamId });
+}
+
+// ============================================
+// Events
+// ============================================
+
+export async function getEvents(
+  context: string,
+  namespace: string,
+  target: EventTarget
+): Promise<KubeEvent[]> {
+  return invokeWithTimeout<KubeEvent[]>('get_events', { context, namespace, target });
+}
+
+export async function startEventStream(
+  streamId: string,
+  context: string,
+  namespace: string,
+  target: EventTarget,
+  onEvent: (event: EventStreamEvent) => void
+): Promise<void> {
+  const channel = new Channel<EventStreamEvent>();
+  channel.onmessage = onEvent;
+  return invokeWithTimeout<void>('start_event_stream', {
+    streamId,
+    context,
+    namespace,
+    target,
+    onEvent: channel,
+  });
+}
+
+export async function stopEventStream(streamId: string): Promise<boolean> {
+  return invoke<boolean>('stop_event_stream', { streamId });
+}
+
+// ============================================
+// Metrics
+// ============================================
+
+// Rejects with kind 'MetricsUnavailable' when metrics-server is missing
+export async function getPodMetrics(
+  context: string,
+  namespace: string,
+  podName: string
+): Promise<PodMetrics> {
+  return invokeWithTimeout<PodMetrics>('get_pod_metrics', { context, namespace, podName });
+}
+
+export async function getDeploymentMetrics(
+  context: string,
+  namespace: string,
+  deployment: string
+): Promise<PodMetrics[]> {
+  return invokeWithTimeout<PodMetrics[]>('get_deployment_metrics', {
+    context,
+    namespace,
+    deployment,
+  });
+}
+
+export async function getWorkloadMetrics(
+  context: string,
+  namespace: string,
+  kind: WorkloadKind,
+  name: string
+): Promise<PodMetrics[]> {
+  return invokeWithTimeout<PodMetrics[]>('get_workload_metrics', { context, namespace, kind, name });
+}
+
 // ============================================
 // Time Range Helpers
 // ============================================
@@ -167,3 +487,15 @@ export function getTimeRangeLabel(range: TimeRange): string {
   };
   return labels[range];
 }
+
+// ============================================
+// Settings
+// ============================================
+
+export async function getSettings(): Promise<AppSettings> {
+  return invoke<AppSettings>('get_settings');
+}
+
+export async function updateSettings(settings: AppSettings): Promise<AppSettings> {
+  return invoke<AppSettings>('update_settings', { settings });
+}
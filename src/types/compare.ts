@@ -0,0 +1,60 @@
+import type { WorkloadKind } from './kubernetes';
+import type { LogSearchFailure } from './logs';
+
+// Logs to compare: whose, and over which time range
+export interface LogSelection {
+  target: CompareTarget;
+  since_seconds?: number;
+  // Absolute range (RFC3339), as in LogSearchOptions
+  since_time?: string;
+  until_time?: string;
+}
+
+export type CompareTarget =
+  // One pod, or a single container of it
+  | { kind: 'pod'; name: string; container?: string }
+  // The pods of one rollout revision of a deployment
+  | { kind: 'revision'; deployment: string; revision: number }
+  | { kind: 'workload'; workload: WorkloadKind; name: string };
+
+export interface CompareOptions {
+  // Occurrences needed to report a template as new or vanished (default 3)
+  min_count?: number;
+  // Factor by which a template's rate must grow to be reported as increased (default 2)
+  min_ratio?: number;
+  // Maximum number of changes returned (default 100)
+  limit?: number;
+  // Lines fetched per container (default 5000)
+  tail_lines?: number;
+}
+
+export interface LogComparison {
+  baseline: LogProfile;
+  candidate: LogProfile;
+  // New templates first, then increased, then vanished
+  changes: TemplateChange[];
+}
+
+export interface LogProfile {
+  pods: string[];
+  total_entries: number;
+  // Entries per level; entries without a level count as NONE
+  level_counts: Record<string, number>;
+  template_count: number;
+  failures: LogSearchFailure[];
+}
+
+export type TemplateChangeKind = 'new' | 'increased' | 'vanished';
+
+export interface TemplateChange {
+  change: TemplateChangeKind;
+  // Message with variable parts replaced by placeholders such as <num> or <uuid>
+  template: string;
+  example: string;
+  level: string | null;
+  baseline_count: number;
+  candidate_count: number;
+  // Occurrences per 1000 entries of each side
+  baseline_rate: number;
+  candidate_rate: number;
+}
@@ -0,0 +1,28 @@
+import type { K8sError } from './kubernetes';
+
+// Objects whose events to list or watch
+export type EventTarget =
+  | { kind: 'pod'; name: string }
+  // The Deployment itself, its ReplicaSets and their pods
+  | { kind: 'deployment'; name: string }
+  | { kind: 'namespace' };
+
+export interface KubeEvent {
+  event_type: 'Normal' | 'Warning' | string;
+  reason: string | null;
+  message: string | null;
+  object_kind: string | null;
+  object_name: string | null;
+  count: number | null;
+  first_timestamp: string | null;
+  last_timestamp: string | null;
+  // Last occurrence in UTC, same format as LogEntry.timestamp_utc
+  timestamp_utc: string | null;
+  component: string | null;
+}
+
+export type EventStreamEvent =
+  | { event: 'initial'; data: KubeEvent[] }
+  | { event: 'event'; data: KubeEvent }
+  | { event: 'error'; data: K8sError }
+  | { event: 'ended' };
@@ -1,7 +1,10 @@
+import type { QuerySpan } from './logs';
+
 export interface ClusterInfo {
   name: string;
   server: string;
   is_current: boolean;
+  source_file: string | null;
 }
 
 export interface NamespaceInfo {
@@ -30,8 +33,28 @@ export interface DeploymentDetails {
   creation_timestamp: string | null;
   labels: Record<string, string>;
   annotations: Record<string, string>;
+  // matchLabels of the selector only
   selector: Record<string, string>;
+  // The full selector, matchExpressions included, e.g. "app=api,tier in (web,worker)"
+  selector_string: string;
   conditions: DeploymentCondition[];
+  // ReplicaSets owned by the deployment, newest revision first
+  revisions: DeploymentRevision[];
+}
+
+// A rollout revision of a deployment, i.e. one of its ReplicaSets
+export interface DeploymentRevision {
+  // 0 when the revision annotation is missing
+  revision: number;
+  replica_set: string;
+  images: string[];
+  creation_timestamp: string | null;
+  change_cause: string | null;
+  replicas: number;
+  ready_replicas: number;
+  available_replicas: number;
+  // Whether this is the revision the deployment currently rolls out
+  current: boolean;
 }
 
 export interface DeploymentCondition {
@@ -43,6 +66,42 @@ export interface DeploymentCondition {
   last_transition_time: string | null;
 }
 
+export type WorkloadKind =
+  | 'Deployment'
+  | 'StatefulSet'
+  | 'DaemonSet'
+  | 'ReplicaSet'
+  | 'Job'
+  | 'CronJob';
+
+export interface WorkloadInfo {
+  kind: WorkloadKind;
+  name: string;
+  namespace: string;
+  replicas: number;
+  available_replicas: number;
+  ready_replicas: number;
+}
+
+export interface WorkloadDetails {
+  kind: WorkloadKind;
+  name: string;
+  namespace: string;
+  replicas: number;
+  available_replicas: number;
+  ready_replicas: number;
+  creation_timestamp: string | null;
+  labels: Record<string, string>;
+  annotations: Record<string, string>;
+  // matchLabels of the selector only
+  selector: Record<string, string>;
+  // The full selector, matchExpressions included; null for CronJobs
+  selector_string: string | null;
+  conditions: DeploymentCondition[];
+  schedule: string | null;
+  suspended: boolean | null;
+}
+
 export interface PodInfo {
   name: string;
   namespace: string;
@@ -53,6 +112,14 @@ export interface PodInfo {
   ip: string;
   node: string;
   containers: string[];
+  container_sources: ContainerSource[];
+}
+
+export type ContainerKind = 'init' | 'sidecar' | 'regular' | 'ephemeral';
+
+export interface ContainerSource {
+  name: string;
+  kind: ContainerKind;
 }
 
 export interface PodDetails {
@@ -70,15 +137,26 @@ export interface PodDetails {
 
 export interface ContainerDetails {
   name: string;
+  kind: ContainerKind;
   image: string;
   ready: boolean;
   restart_count: number;
   state: string;
+  last_state: ContainerTermination | null;
   env_vars: EnvVar[];
   ports: ContainerPort[];
   resources: ResourceRequirements;
 }
 
+export interface ContainerTermination {
+  exit_code: number;
+  reason: string | null;
+  signal: number | null;
+  message: string | null;
+  started_at: string | null;
+  finished_at: string | null;
+}
+
 export interface EnvVar {
   name: string;
   value: string;
@@ -107,3 +185,37 @@ export interface PodCondition {
 }
 
 export type PodStatus = 'Running' | 'Pending' | 'Succeeded' | 'Failed' | 'Unknown' | 'CrashLoopBackOff' | 'ImagePullBackOff' | 'ErrImagePull' | 'ContainerCreating' | 'Terminating' | 'Evicted' | 'UnexpectedAdmissionError' | 'ContainerStatusUnknown';
+
+export type ErrorKind =
+  | 'Unauthorized'
+  | 'Forbidden'
+  | 'NotFound'
+  | 'Conflict'
+  | 'Api'
+  | 'Timeout'
+  | 'Connection'
+  | 'AuthPlugin'
+  | 'Kubeconfig'
+  // Search query that doesn't parse, see K8sError.span
+  | 'InvalidQuery'
+  | 'Parse'
+  | 'Settings'
+  | 'Io'
+  // metrics-server is not installed or not responding
+  | 'MetricsUnavailable'
+  | 'Cancelled';
+
+// Shape of errors rejected by backend commands
+export interface K8sError {
+  kind: ErrorKind;
+  message: string;
+  status: number | null;
+  reason: string | null;
+  api_message: string | null;
+  context: string | null;
+  namespace: string | null;
+  resource: string | null;
+  retryable: boolean;
+  // Set for invalid search queries, so the query input can be highlighted
+  span: QuerySpan | null;
+}
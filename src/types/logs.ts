@@ -1,20 +1,120 @@
+import type { ContainerKind, K8sError } from './kubernetes';
+
 export interface LogEntry {
+  // Timestamp as written in the line, or the kubelet's
   timestamp: string | null;
+  // The same instant in UTC, RFC 3339 with nanoseconds; sorts as a string
+  timestamp_utc: string | null;
   level: LogLevel | null;
   message: string;
   raw: string;
   is_json: boolean;
+  // For events, the object the event is about
   pod_name: string;
   container_name: string;
+  source: LogSource;
+  // Flattened JSON keys (dot paths), or the remaining keys of a logfmt line
+  fields: Record<string, string>;
+  logger: string | null;
+  caller: string | null;
+  trace_id: string | null;
+  span_id: string | null;
+  error: string | null;
+  stack: string | null;
+  // Original ANSI coloring of message
+  spans: StyledSpan[] | null;
+}
+
+// Lines returned by pollPodLogs
+export interface LogPage {
+  entries: LogEntry[];
+  // Opaque position to pass to the next call; null until a line was seen
+  cursor: string | null;
+}
+
+export interface StyledSpan {
+  text: string;
+  // Basic color name ('red', 'bright_blue') or '#rrggbb'
+  fg: string | null;
+  bg: string | null;
+  bold: boolean;
+  dim: boolean;
+  italic: boolean;
+  underline: boolean;
 }
 
 export interface LogSearchResult {
   pod_name: string;
   container_name: string;
+  container_kind: ContainerKind;
   total_matches: number;
   entries: LogEntry[];
 }
 
+export interface LogSearchFailure {
+  pod_name: string;
+  container_name: string;
+  error: K8sError;
+}
+
+export interface LogSearchResponse {
+  results: LogSearchResult[];
+  failures: LogSearchFailure[];
+}
+
+export interface LogSearchProgress {
+  search_id: string | null;
+  completed: number;
+  total: number;
+  result: LogSearchResult | null;
+  failure: LogSearchFailure | null;
+}
+
+export interface LogSearchOptions {
+  keyword?: string;
+  log_level?: string;
+  since_seconds?: number;
+  // RFC 3339 bounds of an absolute range; since_time excludes since_seconds
+  since_time?: string;
+  until_time?: string;
+  query?: string;
+  previous?: boolean;
+  search_id?: string;
+  parallelism?: number;
+  multiline?: MultilineOptions;
+}
+
+// Folding of stack traces and other continuation lines, on by default
+export interface MultilineOptions {
+  enabled?: boolean;
+  continuation_patterns?: string[];
+  max_lines?: number;
+}
+
+// Character offsets of the offending part of a search query
+export interface QuerySpan {
+  start: number;
+  end: number;
+}
+
+export type LogStreamEvent =
+  | { event: 'entries'; data: LogEntry[] }
+  | { event: 'pod_attached'; data: string }
+  | { event: 'pod_detached'; data: string }
+  | { event: 'error'; data: K8sError }
+  | { event: 'ended' };
+
+export interface LogStreamOptions {
+  container?: string;
+  tail_lines?: number;
+  multiline?: MultilineOptions;
+  // Interleave the pod's or deployment's events (not for selector streams)
+  include_events?: boolean;
+}
+
+// A container log line, or a Kubernetes event interleaved with the logs
+export type LogSource = 'container' | 'event';
+
 export type LogLevel = 'ERROR' | 'WARN' | 'INFO' | 'DEBUG';
 
 export const LOG_LEVELS: LogLevel[] = ['ERROR', 'WARN', 'INFO', 'DEBUG'];
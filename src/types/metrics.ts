@@ -0,0 +1,22 @@
+// Resource usage of a pod as reported by metrics-server
+export interface PodMetrics {
+  pod_name: string;
+  // When the usage was sampled; null when no sample exists yet
+  timestamp: string | null;
+  // Length of the sampling window, e.g. "15s"
+  window: string | null;
+  containers: ContainerMetrics[];
+}
+
+export interface ContainerMetrics {
+  name: string;
+  cpu_millicores: number | null;
+  memory_bytes: number | null;
+  cpu_request_millicores: number | null;
+  cpu_limit_millicores: number | null;
+  memory_request_bytes: number | null;
+  memory_limit_bytes: number | null;
+  // Usage as a percentage of the limit, when the container has one
+  cpu_percent_of_limit: number | null;
+  memory_percent_of_limit: number | null;
+}
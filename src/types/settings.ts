@@ -0,0 +1,24 @@
+export interface AppSettings {
+  extra_kubeconfig_paths: string[];
+  parser_rules: ParserRule[];
+  level_aliases: Record<string, string>;
+  parser_assignments: ParserAssignment[];
+}
+
+/** A named line format. Named groups `timestamp`, `level` and `message` fill
+ * those fields, other named groups become structured fields. */
+export interface ParserRule {
+  name: string;
+  pattern: string;
+}
+
+/** Assigns a parser rule to containers; criteria may use `*` and `?` */
+export interface ParserAssignment {
+  /** A parser rule, a built-in format (klog, nginx, apache, envoy, postgres,
+   * redis) or "plain" */
+  parser: string;
+  namespace: string | null;
+  workload: string | null;
+  container: string | null;
+  image: string | null;
+}